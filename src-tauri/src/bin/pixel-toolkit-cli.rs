@@ -0,0 +1,4 @@
+// Headless builds, imports and checks for scripts and build machines
+fn main() -> std::process::ExitCode {
+    pixel_toolkit_lib::cli::run()
+}
@@ -0,0 +1,408 @@
+// Headless entry point used by the `pixel-toolkit-cli` binary. It runs the
+// same core code as the app, without a window or a registered project list.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use crate::core::{
+    events::HeadlessSink,
+    export::{find_material_dirs, load_material, publish::find_profile, publish::publish},
+    import::{
+        pack::{copy_and_process, unzip_and_process, ImportOptions},
+        rules::CompiledRules,
+        ImportReport,
+    },
+    project::{
+        load_project_yml,
+        profiles::validate_profile,
+        structs::{InputFormat, ProjectYml},
+    },
+    store,
+};
+
+// Finished, nothing failed
+pub const EXIT_OK: u8 = 0;
+// Finished, but some materials or files failed or the project has problems
+pub const EXIT_FAILURES: u8 = 1;
+// Unknown command or missing arguments
+pub const EXIT_USAGE: u8 = 2;
+// The project, profile or pack given can't be used
+pub const EXIT_INVALID_INPUT: u8 = 3;
+// Stopped by an I/O or encoding error
+pub const EXIT_ERROR: u8 = 4;
+
+const USAGE: &str = "Usage:
+  pixel-toolkit-cli build --project <dir> --profile <name> --out <file>
+  pixel-toolkit-cli import --pack <zip or dir> --project <dir> [--format <format>] [--keep-originals]
+  pixel-toolkit-cli validate --project <dir>
+
+Formats: labpbr-1.3 (default), labpbr-1.2, oldpbr, or raw for a project whose
+materials keep each channel in its own file";
+
+// Flags that don't take a value
+const FLAGS: [&str; 2] = ["keep-originals", "help"];
+
+struct Failure {
+    code: u8,
+    message: String,
+}
+
+fn usage_error(message: impl Into<String>) -> Failure {
+    Failure {
+        code: EXIT_USAGE,
+        message: format!("{}\n\n{}", message.into(), USAGE),
+    }
+}
+
+fn invalid_input(message: impl Into<String>) -> Failure {
+    Failure {
+        code: EXIT_INVALID_INPUT,
+        message: message.into(),
+    }
+}
+
+fn error(message: impl Into<String>) -> Failure {
+    Failure {
+        code: EXIT_ERROR,
+        message: message.into(),
+    }
+}
+
+struct Args {
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    // Accepts both `--key value` and `--key=value`
+    fn parse(args: &[String]) -> Result<Self, Failure> {
+        let mut options = HashMap::new();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .ok_or_else(|| usage_error(format!("Unexpected argument: {}", arg)))?;
+
+            let (key, value) = match key.split_once('=') {
+                Some((key, value)) => (key, value.to_string()),
+                None if FLAGS.contains(&key) => (key, String::new()),
+                None => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| usage_error(format!("Missing value for --{}", key)))?;
+                    (key, value.clone())
+                }
+            };
+            options.insert(key.to_string(), value);
+        }
+
+        Ok(Args { options })
+    }
+
+    fn required(&self, key: &str) -> Result<&str, Failure> {
+        self.options
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| usage_error(format!("Missing --{}", key)))
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.options.contains_key(key)
+    }
+}
+
+pub fn run() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    ExitCode::from(run_with_args(&args))
+}
+
+pub fn run_with_args(args: &[String]) -> u8 {
+    let Some((command, rest)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        return EXIT_USAGE;
+    };
+
+    let result = Args::parse(rest).and_then(|args| match command.as_str() {
+        _ if args.flag("help") => {
+            println!("{}", USAGE);
+            Ok(EXIT_OK)
+        }
+        "build" => build(&args),
+        "import" => import(&args),
+        "validate" => validate(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(EXIT_OK)
+        }
+        _ => Err(usage_error(format!("Unknown command: {}", command))),
+    });
+
+    match result {
+        Ok(code) => code,
+        Err(failure) => {
+            eprintln!("{}", failure.message);
+            failure.code
+        }
+    }
+}
+
+fn load_project(project_dir: &Path) -> Result<ProjectYml, Failure> {
+    if !project_dir.join("project.yml").exists() {
+        return Err(invalid_input(format!(
+            "No project.yml in {}",
+            project_dir.display()
+        )));
+    }
+    load_project_yml(project_dir).map_err(|e| invalid_input(e.to_string()))
+}
+
+fn build(args: &Args) -> Result<u8, Failure> {
+    let project_dir = PathBuf::from(args.required("project")?);
+    let profile_name = args.required("profile")?;
+    let out_file = PathBuf::from(args.required("out")?);
+
+    let project_yml = load_project(&project_dir)?;
+    let profile = find_profile(&project_dir, &project_yml, profile_name)
+        .map_err(|e| invalid_input(e.to_string()))?;
+
+    let summary = publish(
+        &project_dir,
+        &project_yml,
+        &profile,
+        &out_file,
+        &HeadlessSink::default(),
+    )
+    .map_err(|e| error(format!("Failed to build {}: {}", profile_name, e)))?;
+
+    println!(
+        "Built {}: {} materials, {} files",
+        out_file.display(),
+        summary.materials,
+        summary.files
+    );
+    for failure in &summary.failed {
+        eprintln!("Failed {}: {}", failure.path, failure.error);
+    }
+
+    Ok(if summary.failed.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_FAILURES
+    })
+}
+
+fn import(args: &Args) -> Result<u8, Failure> {
+    let pack = PathBuf::from(args.required("pack")?);
+    let project_dir = PathBuf::from(args.required("project")?);
+
+    let mut options = ImportOptions {
+        keep_originals: args.flag("keep-originals"),
+        ..ImportOptions::default()
+    };
+    if let Some(format) = args.options.get("format") {
+        options.format = serde_yaml::from_str(format)
+            .map_err(|_| usage_error(format!("Unknown format: {}", format)))?;
+    }
+
+    if !pack.exists() {
+        return Err(invalid_input(format!("Pack not found: {}", pack.display())));
+    }
+
+    fs::create_dir_all(&project_dir)
+        .map_err(|e| error(format!("Failed to create {}: {}", project_dir.display(), e)))?;
+
+    let project_name = project_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "My New Project".to_string());
+
+    // The pack's own pack.mcmeta or manifest.json fills in the rest
+    if !project_dir.join("project.yml").exists() {
+        let project_yml = ProjectYml::new(project_name.clone(), None, InputFormat::Raw);
+        store::write_yaml(&project_dir.join("project.yml"), &project_yml)
+            .map_err(|e| error(format!("Failed to write project.yml: {}", e)))?;
+    }
+
+    let project_id = uuid::Uuid::new_v4().to_string();
+    let sink = Arc::new(HeadlessSink::default());
+    let result = if pack.is_dir() {
+        copy_and_process(&pack, &project_dir, project_id, project_name, options, sink)
+    } else {
+        unzip_and_process(&pack, &project_dir, project_id, project_name, options, sink)
+    };
+    let report = result.map_err(|e| error(format!("Failed to import pack: {}", e)))?;
+
+    print_import_report(&report);
+
+    Ok(if report.failed.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_FAILURES
+    })
+}
+
+fn print_import_report(report: &ImportReport) {
+    println!(
+        "Imported pack: {} converted, {} skipped, {} failed",
+        report.converted.len(),
+        report.skipped.len(),
+        report.failed.len()
+    );
+    for failure in &report.failed {
+        eprintln!("Failed {}: {}", failure.path, failure.error);
+    }
+}
+
+// Reads everything a build would read and lists what can't be used
+fn validate(args: &Args) -> Result<u8, Failure> {
+    let project_dir = PathBuf::from(args.required("project")?);
+    let project_yml = load_project(&project_dir)?;
+    let mut problems: Vec<String> = Vec::new();
+
+    let mut names: Vec<&str> = Vec::new();
+    for profile in project_yml.profiles.iter().flatten() {
+        if names.contains(&profile.name.as_str()) {
+            problems.push(format!(
+                "project.yml: profile {} is defined twice",
+                profile.name
+            ));
+        }
+        names.push(&profile.name);
+
+        if let Err((field, message)) = validate_profile(profile) {
+            problems.push(format!(
+                "project.yml: profile {}: {}: {}",
+                profile.name, field, message
+            ));
+        }
+    }
+
+    if let Some(rules) = &project_yml.import {
+        if let Err(e) = CompiledRules::new(rules) {
+            problems.push(format!("project.yml: {}", e));
+        }
+    }
+
+    let material_dirs = find_material_dirs(&project_dir);
+    for material_dir in &material_dirs {
+        let rel_dir = material_dir
+            .strip_prefix(&project_dir)
+            .unwrap_or(material_dir)
+            .display();
+
+        // Reads mat.yml and every texture, packed or not
+        if let Err(e) = load_material(material_dir, project_yml.input.format) {
+            problems.push(format!("{}: {}", rel_dir, e));
+        }
+    }
+
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    println!(
+        "Checked {} materials and {} profiles: {} problems",
+        material_dirs.len(),
+        names.len(),
+        problems.len()
+    );
+
+    Ok(if problems.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_FAILURES
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> u8 {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        run_with_args(&args)
+    }
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let project_yml = ProjectYml::new("Test".to_string(), None, InputFormat::Raw);
+        store::write_yaml(&dir.path().join("project.yml"), &project_yml).unwrap();
+        dir
+    }
+
+    #[test]
+    fn usage_errors() {
+        assert_eq!(run(&[]), EXIT_USAGE);
+        assert_eq!(run(&["publish"]), EXIT_USAGE);
+        assert_eq!(run(&["validate"]), EXIT_USAGE);
+        assert_eq!(run(&["validate", "project"]), EXIT_USAGE);
+        assert_eq!(run(&["build", "--project"]), EXIT_USAGE);
+        assert_eq!(
+            run(&[
+                "import",
+                "--pack",
+                "a.zip",
+                "--project",
+                "b",
+                "--format",
+                "png"
+            ]),
+            EXIT_USAGE
+        );
+        assert_eq!(run(&["build", "--help"]), EXIT_OK);
+    }
+
+    #[test]
+    fn missing_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing").to_string_lossy().to_string();
+
+        assert_eq!(
+            run(&["validate", "--project", &missing]),
+            EXIT_INVALID_INPUT
+        );
+        assert_eq!(
+            run(&[
+                "build",
+                "--project",
+                &missing,
+                "--profile",
+                "a",
+                "--out=a.zip"
+            ]),
+            EXIT_INVALID_INPUT
+        );
+
+        let project = project();
+        let project_dir = project.path().to_string_lossy().to_string();
+        assert_eq!(
+            run(&[
+                "build",
+                "--project",
+                &project_dir,
+                "--profile",
+                "a",
+                "--out=a.zip"
+            ]),
+            EXIT_INVALID_INPUT
+        );
+    }
+
+    #[test]
+    fn validate_reports_broken_materials() {
+        let project = project();
+        let project_dir = project.path().to_string_lossy().to_string();
+        let material_dir = project.path().join("stone");
+        fs::create_dir(&material_dir).unwrap();
+        fs::write(material_dir.join("mat.yml"), "smooth:\n  value: 10\n").unwrap();
+
+        assert_eq!(run(&["validate", "--project", &project_dir]), EXIT_OK);
+
+        fs::write(material_dir.join("mat.yml"), "smooth: [broken\n").unwrap();
+        assert_eq!(run(&["validate", "--project", &project_dir]), EXIT_FAILURES);
+    }
+}
@@ -0,0 +1,279 @@
+// LabPBR 1.3 channel codec, see https://shaderlabs.org/wiki/LabPBR_Material_Standard
+//
+// _n.png: R/G normal X/Y, B ambient occlusion, A height
+// _s.png: R perceptual smoothness, G f0 (0-229) or hardcoded metal (230-255),
+//         B porosity (0-64) or subsurface scattering (65-255), A emission (255 = none)
+//
+// The decoded channel files a material folder holds are:
+// - smooth.png: perceptual smoothness as is
+// - f0.png: the f0 value (0-229), 0 wherever a hardcoded metal is used
+// - hcm.png: the hardcoded metal index (230-255), 0 for dielectrics
+// - porosity.png / sss.png: rescaled to 0-255, sss is never 0 inside its range
+// - emissive.png: 0 for no emission, 1-255 for the 255 emission levels
+// - ao.png / height.png: the raw blue / alpha values
+
+use image::{GrayImage, Luma, Rgb, RgbImage, RgbaImage};
+
+pub const HCM_MIN: u8 = 230;
+pub const DEFAULT_F0: u8 = 10;
+const POROSITY_MAX: u8 = 64;
+const SSS_MIN: u8 = 65;
+
+// How far the blue channel may stray from the reconstructed Z for a normal
+// map to count as an old style XYZ map instead of XY + AO
+const NORMAL_Z_TOLERANCE: i16 = 8;
+
+pub fn decode_f0(value: u8) -> (u8, u8) {
+    if value < HCM_MIN {
+        (value, 0)
+    } else {
+        (0, value)
+    }
+}
+
+pub fn encode_f0(f0: Option<u8>, hcm: Option<u8>, metal: Option<u8>) -> u8 {
+    match (hcm, metal) {
+        (Some(hcm), _) if hcm >= HCM_MIN => hcm,
+        // Albedo based metal
+        (_, Some(metal)) if metal >= 128 => 255,
+        _ => f0.unwrap_or(DEFAULT_F0).min(HCM_MIN - 1),
+    }
+}
+
+pub fn decode_porosity_sss(value: u8) -> (u8, u8) {
+    if value <= POROSITY_MAX {
+        let porosity = (value as u32 * 255 + POROSITY_MAX as u32 / 2) / POROSITY_MAX as u32;
+        (porosity as u8, 0)
+    } else {
+        // 65 still means "some" subsurface scattering, so the range starts at 1
+        let range = (255 - SSS_MIN) as u32;
+        let sss = ((value - SSS_MIN) as u32 * 254 + range / 2) / range + 1;
+        (0, sss as u8)
+    }
+}
+
+pub fn encode_porosity_sss(porosity: Option<u8>, sss: Option<u8>) -> u8 {
+    match (porosity, sss) {
+        (_, Some(sss)) if sss > 0 => {
+            let range = (255 - SSS_MIN) as u32;
+            (SSS_MIN as u32 + ((sss - 1) as u32 * range + 127) / 254) as u8
+        }
+        (Some(porosity), _) => ((porosity as u32 * POROSITY_MAX as u32 + 127) / 255) as u8,
+        _ => 0,
+    }
+}
+
+pub fn decode_emissive(value: u8) -> u8 {
+    // 255 is "no emission", not the brightest value
+    if value == 255 {
+        0
+    } else {
+        value + 1
+    }
+}
+
+pub fn encode_emissive(emissive: Option<u8>) -> u8 {
+    match emissive {
+        Some(value) if value > 0 => value - 1,
+        _ => 255,
+    }
+}
+
+pub fn encode_smoothness(smooth: Option<u8>, rough: Option<u8>) -> u8 {
+    match (smooth, rough) {
+        (Some(smooth), _) => smooth,
+        (None, Some(rough)) => 255 - rough,
+        (None, None) => 0,
+    }
+}
+
+pub fn encode_height(value: u8) -> u8 {
+    // A height of 0 breaks parallax in most shaders
+    value.max(1)
+}
+
+// Rebuilds the Z component of a unit normal from its X and Y
+pub fn normal_z(x: u8, y: u8) -> u8 {
+    let x = x as f32 / 255.0 * 2.0 - 1.0;
+    let y = y as f32 / 255.0 * 2.0 - 1.0;
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+
+    ((z * 0.5 + 0.5) * 255.0).round() as u8
+}
+
+// Older tools write the normal Z into blue instead of ambient occlusion
+pub fn blue_is_normal_z(img: &RgbaImage) -> bool {
+    let total = img.pixels().len().max(1);
+    let matching = img
+        .pixels()
+        .filter(|p| (p[2] as i16 - normal_z(p[0], p[1]) as i16).abs() <= NORMAL_Z_TOLERANCE)
+        .count();
+
+    matching * 10 >= total * 9
+}
+
+pub struct NormalChannels {
+    pub normal: RgbImage,
+    pub occlusion: Option<GrayImage>,
+    pub height: Option<GrayImage>,
+}
+
+pub struct SpecularChannels {
+    pub smooth: GrayImage,
+    pub f0: GrayImage,
+    pub hcm: Option<GrayImage>,
+    pub porosity: Option<GrayImage>,
+    pub sss: Option<GrayImage>,
+    pub emissive: Option<GrayImage>,
+}
+
+pub fn channel_map(img: &RgbaImage, decode: impl Fn(&image::Rgba<u8>) -> u8) -> GrayImage {
+    GrayImage::from_fn(img.width(), img.height(), |x, y| {
+        Luma([decode(img.get_pixel(x, y))])
+    })
+}
+
+// Channels that only carry their "nothing here" value aren't worth a file
+pub fn non_empty(img: GrayImage, empty: u8) -> Option<GrayImage> {
+    img.pixels().any(|p| p[0] != empty).then_some(img)
+}
+
+pub fn decode_normal(img: &RgbaImage) -> NormalChannels {
+    let normal = RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let p = img.get_pixel(x, y);
+        Rgb([p[0], p[1], normal_z(p[0], p[1])])
+    });
+
+    let occlusion = if blue_is_normal_z(img) {
+        None
+    } else {
+        non_empty(channel_map(img, |p| p[2]), 255)
+    };
+
+    NormalChannels {
+        normal,
+        occlusion,
+        height: non_empty(channel_map(img, |p| p[3]), 255),
+    }
+}
+
+// LabPBR 1.2 and older keep the normal's Z in blue, so there is no AO to read
+pub fn decode_normal_v1_2(img: &RgbaImage) -> NormalChannels {
+    let normal = RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let p = img.get_pixel(x, y);
+        Rgb([p[0], p[1], p[2]])
+    });
+
+    NormalChannels {
+        normal,
+        occlusion: None,
+        height: non_empty(channel_map(img, |p| p[3]), 255),
+    }
+}
+
+pub fn decode_specular(img: &RgbaImage) -> SpecularChannels {
+    SpecularChannels {
+        smooth: channel_map(img, |p| p[0]),
+        f0: channel_map(img, |p| decode_f0(p[1]).0),
+        hcm: non_empty(channel_map(img, |p| decode_f0(p[1]).1), 0),
+        porosity: non_empty(channel_map(img, |p| decode_porosity_sss(p[2]).0), 0),
+        sss: non_empty(channel_map(img, |p| decode_porosity_sss(p[2]).1), 0),
+        emissive: non_empty(channel_map(img, |p| decode_emissive(p[3])), 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f0_hcm_round_trip() {
+        for value in 0..=255u8 {
+            let (f0, hcm) = decode_f0(value);
+            assert_eq!(encode_f0(Some(f0), Some(hcm), None), value);
+        }
+    }
+
+    #[test]
+    fn hcm_range() {
+        for value in 230..=255u8 {
+            assert_eq!(decode_f0(value), (0, value));
+        }
+    }
+
+    #[test]
+    fn porosity_sss_round_trip() {
+        for value in 0..=255u8 {
+            let (porosity, sss) = decode_porosity_sss(value);
+            assert_eq!(encode_porosity_sss(Some(porosity), Some(sss)), value);
+        }
+    }
+
+    #[test]
+    fn porosity_sss_ranges() {
+        assert_eq!(decode_porosity_sss(0), (0, 0));
+        assert_eq!(decode_porosity_sss(64), (255, 0));
+        assert_eq!(decode_porosity_sss(65), (0, 1));
+        assert_eq!(decode_porosity_sss(255), (0, 255));
+    }
+
+    #[test]
+    fn emissive_round_trip() {
+        for value in 0..=255u8 {
+            assert_eq!(encode_emissive(Some(decode_emissive(value))), value);
+        }
+        assert_eq!(decode_emissive(255), 0);
+        assert_eq!(decode_emissive(254), 255);
+    }
+
+    #[test]
+    fn smoothness_and_height_round_trip() {
+        for value in 0..=255u8 {
+            assert_eq!(encode_smoothness(Some(value), None), value);
+        }
+        for value in 1..=255u8 {
+            assert_eq!(encode_height(value), value);
+        }
+    }
+
+    #[test]
+    fn normal_z_flat() {
+        assert_eq!(normal_z(128, 128), 255);
+        assert_eq!(normal_z(255, 128), 128);
+    }
+
+    #[test]
+    fn normal_blue_ao() {
+        let xyz = RgbaImage::from_fn(4, 4, |x, y| {
+            let (nx, ny) = (100 + x as u8 * 10, 120 + y as u8 * 5);
+            image::Rgba([nx, ny, normal_z(nx, ny), 255])
+        });
+        assert!(decode_normal(&xyz).occlusion.is_none());
+
+        let ao = RgbaImage::from_pixel(4, 4, image::Rgba([128, 128, 40, 200]));
+        let decoded = decode_normal(&ao);
+        assert_eq!(decoded.occlusion.unwrap().get_pixel(0, 0)[0], 40);
+        assert_eq!(decoded.height.unwrap().get_pixel(0, 0)[0], 200);
+        assert_eq!(decoded.normal.get_pixel(0, 0)[2], 255);
+    }
+
+    #[test]
+    fn specular_texture_round_trip() {
+        let img = RgbaImage::from_fn(16, 16, |x, y| {
+            let v = (y * 16 + x) as u8;
+            image::Rgba([v, v, v, v])
+        });
+        let decoded = decode_specular(&img);
+
+        for (x, y, p) in img.enumerate_pixels() {
+            let get = |c: &Option<GrayImage>| c.as_ref().map(|c| c.get_pixel(x, y)[0]);
+            let encoded = [
+                encode_smoothness(Some(decoded.smooth.get_pixel(x, y)[0]), None),
+                encode_f0(Some(decoded.f0.get_pixel(x, y)[0]), get(&decoded.hcm), None),
+                encode_porosity_sss(get(&decoded.porosity), get(&decoded.sss)),
+                encode_emissive(get(&decoded.emissive)),
+            ];
+            assert_eq!(encoded, p.0);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+pub mod labpbr;
+pub mod oldpbr;
+
+use image::{DynamicImage, GrayImage, Luma, RgbaImage};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::project::structs::InputFormat;
+
+// Channels decoded from packed textures, keyed by their name in TEXTURE_FILES
+pub type DecodedChannels = HashMap<&'static str, DynamicImage>;
+
+pub const NORMAL_SUFFIX: &str = "_n.png";
+pub const SPECULAR_SUFFIX: &str = "_s.png";
+
+fn material_name(material_dir: &Path) -> String {
+    material_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+// Packed layouts keep `<name>.png`, `<name>_n.png` and `<name>_s.png` in the
+// material folder, none of which should be mistaken for a channel file
+pub fn is_packed_file(material_dir: &Path, file_name: &str) -> bool {
+    file_name.ends_with(NORMAL_SUFFIX)
+        || file_name.ends_with(SPECULAR_SUFFIX)
+        || file_name == format!("{}.png", material_name(material_dir))
+}
+
+// Finds the first file matching `pattern`, skipping packed textures unless the project
+// stores separate channel files
+pub fn find_source_file(
+    material_dir: &Path,
+    pattern: &str,
+    format: InputFormat,
+) -> Option<PathBuf> {
+    let regex = regex::Regex::new(pattern).ok()?;
+
+    fs::read_dir(material_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            regex.is_match(&file_name)
+                && (format == InputFormat::Raw || !is_packed_file(material_dir, &file_name))
+        })
+        .map(|entry| entry.path())
+}
+
+fn find_packed(material_dir: &Path, suffix: &str) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(material_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(suffix))
+        .map(|entry| entry.path())
+        .collect();
+
+    files.sort();
+    files.into_iter().next()
+}
+
+fn open_rgba(path: &Path) -> Option<RgbaImage> {
+    image::open(path)
+        .map_err(|e| eprintln!("Error opening image {}: {}", path.to_string_lossy(), e))
+        .ok()
+        .map(|img| img.to_rgba8())
+}
+
+fn insert_gray(channels: &mut DecodedChannels, name: &'static str, img: Option<GrayImage>) {
+    if let Some(img) = img {
+        channels.insert(name, DynamicImage::ImageLuma8(img));
+    }
+}
+
+fn invert(img: &GrayImage) -> GrayImage {
+    let mut inverted = img.clone();
+    image::imageops::invert(&mut inverted);
+    inverted
+}
+
+// Decodes the packed textures of a material folder into separate channels.
+// Raw projects have nothing packed, so they always get an empty map.
+pub fn decode_packed(material_dir: &Path, format: InputFormat) -> DecodedChannels {
+    let mut channels = DecodedChannels::new();
+    if format == InputFormat::Raw {
+        return channels;
+    }
+
+    let color_path = material_dir.join(format!("{}.png", material_name(material_dir)));
+    if let Some(color) = color_path
+        .exists()
+        .then(|| open_rgba(&color_path))
+        .flatten()
+    {
+        let opacity = labpbr::channel_map(&color, |p| p[3]);
+        insert_gray(&mut channels, "opacity", labpbr::non_empty(opacity, 255));
+        channels.insert("color", DynamicImage::ImageRgba8(color));
+    }
+
+    if let Some(normal) = find_packed(material_dir, NORMAL_SUFFIX).and_then(|p| open_rgba(&p)) {
+        let decoded = match format {
+            InputFormat::LabPbr13 => labpbr::decode_normal(&normal),
+            _ => labpbr::decode_normal_v1_2(&normal),
+        };
+
+        channels.insert("normal", DynamicImage::ImageRgb8(decoded.normal));
+        insert_gray(&mut channels, "occlusion", decoded.occlusion);
+        insert_gray(&mut channels, "height", decoded.height);
+    }
+
+    if let Some(specular) = find_packed(material_dir, SPECULAR_SUFFIX).and_then(|p| open_rgba(&p)) {
+        match format {
+            InputFormat::OldPbr => {
+                let decoded = oldpbr::decode_specular(&specular);
+                if let Some(metal) = &decoded.metal {
+                    let converted = oldpbr::metal_mask_to_f0(metal);
+                    insert_gray(&mut channels, "f0", Some(converted.f0));
+                    insert_gray(&mut channels, "hcm", converted.hcm);
+                }
+                insert_gray(&mut channels, "rough", Some(invert(&decoded.smooth)));
+                insert_gray(&mut channels, "smooth", Some(decoded.smooth));
+                insert_gray(&mut channels, "metal", decoded.metal);
+                insert_gray(&mut channels, "emissive", decoded.emissive);
+            }
+            _ => {
+                let decoded = labpbr::decode_specular(&specular);
+                // Any hardcoded metal counts as fully metallic
+                let metal = decoded.hcm.as_ref().map(|hcm| {
+                    GrayImage::from_fn(hcm.width(), hcm.height(), |x, y| {
+                        Luma([if hcm.get_pixel(x, y)[0] >= labpbr::HCM_MIN {
+                            255
+                        } else {
+                            0
+                        }])
+                    })
+                });
+
+                insert_gray(&mut channels, "rough", Some(invert(&decoded.smooth)));
+                insert_gray(&mut channels, "smooth", Some(decoded.smooth));
+                insert_gray(&mut channels, "f0", Some(decoded.f0));
+                insert_gray(&mut channels, "hcm", decoded.hcm);
+                insert_gray(&mut channels, "metal", metal);
+                insert_gray(&mut channels, "porosity", decoded.porosity);
+                insert_gray(&mut channels, "sss", decoded.sss);
+                insert_gray(&mut channels, "emissive", decoded.emissive);
+            }
+        }
+    }
+
+    channels
+}
@@ -0,0 +1,107 @@
+// OldPBR / SEUS specular layout: R smoothness, G metalness, B emission.
+// Normal textures are plain XYZ with the height in alpha.
+//
+// LabPBR has no metalness channel, so the metal mask is converted to f0/HCM:
+// - below METAL_THRESHOLD it spreads linearly over the dielectric f0 range
+//   (DEFAULT_F0 up to 229)
+// - from METAL_THRESHOLD up it becomes the albedo based hardcoded metal (255)
+
+use image::{GrayImage, Luma, RgbaImage};
+
+use super::labpbr::{channel_map, encode_smoothness, non_empty, DEFAULT_F0, HCM_MIN};
+
+pub const METAL_THRESHOLD: u8 = 128;
+const ALBEDO_METAL: u8 = 255;
+const F0_RANGE: u32 = (HCM_MIN - 1 - DEFAULT_F0) as u32;
+const PARTIAL_METAL_MAX: u32 = METAL_THRESHOLD as u32 - 1;
+
+pub struct SpecularChannels {
+    pub smooth: GrayImage,
+    pub metal: Option<GrayImage>,
+    pub emissive: Option<GrayImage>,
+}
+
+pub struct MetalChannels {
+    pub f0: GrayImage,
+    pub hcm: Option<GrayImage>,
+}
+
+// Returns the (f0, hcm) pair for a metalness value
+pub fn metal_to_f0(metal: u8) -> (u8, u8) {
+    if metal >= METAL_THRESHOLD {
+        (0, ALBEDO_METAL)
+    } else {
+        let f0 = DEFAULT_F0 as u32
+            + (metal as u32 * F0_RANGE + PARTIAL_METAL_MAX / 2) / PARTIAL_METAL_MAX;
+        (f0 as u8, 0)
+    }
+}
+
+pub fn f0_to_metal(f0: u8, hcm: u8) -> u8 {
+    if hcm >= HCM_MIN {
+        return 255;
+    }
+
+    let f0 = f0.saturating_sub(DEFAULT_F0) as u32;
+    ((f0 * PARTIAL_METAL_MAX + F0_RANGE / 2) / F0_RANGE).min(PARTIAL_METAL_MAX) as u8
+}
+
+pub fn encode_metal(metal: Option<u8>, f0: Option<u8>, hcm: Option<u8>) -> u8 {
+    match (metal, f0, hcm) {
+        (Some(metal), _, _) => metal,
+        (None, None, None) => 0,
+        (None, f0, hcm) => f0_to_metal(f0.unwrap_or(DEFAULT_F0), hcm.unwrap_or(0)),
+    }
+}
+
+pub fn encode_specular_pixel(
+    smooth: Option<u8>,
+    rough: Option<u8>,
+    metal: u8,
+    emissive: Option<u8>,
+) -> [u8; 4] {
+    [
+        encode_smoothness(smooth, rough),
+        metal,
+        emissive.unwrap_or(0),
+        255,
+    ]
+}
+
+pub fn metal_mask_to_f0(metal: &GrayImage) -> MetalChannels {
+    let convert = |channel: fn((u8, u8)) -> u8| {
+        GrayImage::from_fn(metal.width(), metal.height(), |x, y| {
+            Luma([channel(metal_to_f0(metal.get_pixel(x, y)[0]))])
+        })
+    };
+
+    MetalChannels {
+        f0: convert(|(f0, _)| f0),
+        hcm: non_empty(convert(|(_, hcm)| hcm), 0),
+    }
+}
+
+pub fn decode_specular(img: &RgbaImage) -> SpecularChannels {
+    SpecularChannels {
+        smooth: channel_map(img, |p| p[0]),
+        metal: non_empty(channel_map(img, |p| p[1]), 0),
+        emissive: non_empty(channel_map(img, |p| p[2]), 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metal_round_trip() {
+        for metal in 0..METAL_THRESHOLD {
+            let (f0, hcm) = metal_to_f0(metal);
+            assert!(f0 < HCM_MIN);
+            assert_eq!(f0_to_metal(f0, hcm), metal);
+        }
+        assert_eq!(metal_to_f0(0), (DEFAULT_F0, 0));
+        assert_eq!(metal_to_f0(255), (0, 255));
+        assert_eq!(f0_to_metal(0, 255), 255);
+    }
+}
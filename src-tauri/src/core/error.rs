@@ -0,0 +1,99 @@
+// The error every command returns. The frontend gets it as
+// `{ kind, path, message }` and can switch on `kind`.
+
+use std::{fmt::Display, io, path::Path};
+
+#[derive(Debug, thiserror::Error, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Error {
+    // A file, folder, project or profile that isn't there
+    #[error("{message} ({path})")]
+    NotFound { path: String, message: String },
+    // A file that is there but can't be read as what it should hold
+    #[error("{message} ({path})")]
+    Parse { path: String, message: String },
+    #[error("{message} ({path})")]
+    Io { path: String, message: String },
+    // A file that parses but has a field with the wrong type or value.
+    // `field` is its path in the document, e.g. `normal.method`.
+    #[error("{field}: {message} ({path})")]
+    InvalidField {
+        path: String,
+        field: String,
+        message: String,
+    },
+    // An argument the command can't work with
+    #[error("{message} ({path})")]
+    InvalidInput { path: String, message: String },
+    // Something that is already there or already in use
+    #[error("{message} ({path})")]
+    Conflict { path: String, message: String },
+}
+
+pub type CommandResult<T> = Result<T, Error>;
+
+fn path_string(path: impl AsRef<Path>) -> String {
+    path.as_ref().to_string_lossy().to_string()
+}
+
+impl Error {
+    pub fn not_found(path: impl AsRef<Path>, message: impl Display) -> Self {
+        Error::NotFound {
+            path: path_string(path),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn parse(path: impl AsRef<Path>, message: impl Display) -> Self {
+        Error::Parse {
+            path: path_string(path),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn invalid_field(
+        path: impl AsRef<Path>,
+        field: impl Display,
+        message: impl Display,
+    ) -> Self {
+        Error::InvalidField {
+            path: path_string(path),
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn invalid_input(path: impl AsRef<Path>, message: impl Display) -> Self {
+        Error::InvalidInput {
+            path: path_string(path),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn conflict(path: impl AsRef<Path>, message: impl Display) -> Self {
+        Error::Conflict {
+            path: path_string(path),
+            message: message.to_string(),
+        }
+    }
+
+    // Missing files become NotFound, everything else stays an I/O error
+    pub fn io(path: impl AsRef<Path>, error: io::Error) -> Self {
+        let path = path_string(path);
+        let message = error.to_string();
+
+        match error.kind() {
+            io::ErrorKind::NotFound => Error::NotFound { path, message },
+            io::ErrorKind::AlreadyExists => Error::Conflict { path, message },
+            _ => Error::Io { path, message },
+        }
+    }
+
+    // Anything else that went wrong while working on `path`
+    pub fn other(path: impl AsRef<Path>, error: impl Display) -> Self {
+        Error::Io {
+            path: path_string(path),
+            message: error.to_string(),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// Everything core code needs from whoever hosts it: somewhere to send events
+// and toasts, and the folder holding projects.yml. The app passes its
+// `tauri::AppHandle`, the CLI and tests pass their own sink.
+
+use std::path::PathBuf;
+use tauri::{Emitter, Manager};
+
+pub trait EventSink: Send + Sync {
+    // Sends a named event, e.g. `import-progress`, to the frontend
+    fn emit(&self, event: &str, payload: serde_json::Value);
+
+    // A short message meant for the user
+    fn toast(&self, message: &str);
+
+    // Where projects.yml lives, None when there's no project list
+    fn config_dir(&self) -> Option<PathBuf>;
+}
+
+impl EventSink for tauri::AppHandle {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Err(e) = Emitter::emit(self, event, payload) {
+            eprintln!("Failed to emit {}: {}", event, e);
+        }
+    }
+
+    fn toast(&self, message: &str) {
+        EventSink::emit(self, "simple-toast", message.into());
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.path().app_data_dir().ok()
+    }
+}
+
+// For running without a window: events are dropped, toasts go to stderr
+#[derive(Default)]
+pub struct HeadlessSink {
+    pub config_dir: Option<PathBuf>,
+}
+
+impl EventSink for HeadlessSink {
+    fn emit(&self, _event: &str, _payload: serde_json::Value) {}
+
+    fn toast(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.config_dir.clone()
+    }
+}
+
+// Keeps every event and toast so tests can look at them
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingSink {
+    pub events: std::sync::Mutex<Vec<(String, serde_json::Value)>>,
+    pub toasts: std::sync::Mutex<Vec<String>>,
+    // Called with every event before it's recorded
+    pub on_emit: Option<Box<dyn Fn(&str, &serde_json::Value) + Send + Sync>>,
+}
+
+#[cfg(test)]
+impl RecordingSink {
+    pub fn events(&self, event: &str) -> Vec<serde_json::Value> {
+        let events = self.events.lock().unwrap();
+        events
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl EventSink for RecordingSink {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Some(on_emit) = &self.on_emit {
+            on_emit(event, &payload);
+        }
+        self.events
+            .lock()
+            .unwrap()
+            .push((event.to_string(), payload));
+    }
+
+    fn toast(&self, message: &str) {
+        self.toasts.lock().unwrap().push(message.to_string());
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        None
+    }
+}
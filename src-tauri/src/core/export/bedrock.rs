@@ -0,0 +1,345 @@
+// Bedrock Edition RTX encoder, see
+// https://learn.microsoft.com/en-us/minecraft/creator/documents/rtx/texturesetsconcepts
+//
+// <name>_mer.png: R metalness, G emissive, B perceptual roughness, A subsurface (MERS only)
+// <name>_normal.png or <name>_heightmap.png, and a <name>.texture_set.json tying them together
+
+use anyhow::Result;
+use image::RgbaImage;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+use super::{png_bytes, sample, zip_path, Material};
+use crate::core::project::structs::{Profile, ProjectYml};
+
+const TEXTURE_SET_VERSION: &str = "1.16.100";
+// Subsurface scattering in the fourth MER channel needs a newer texture set
+const TEXTURE_SET_VERSION_MERS: &str = "1.21.30";
+
+pub fn encode_metalness(metal: Option<u8>, hcm: Option<u8>) -> u8 {
+    match (metal, hcm) {
+        (Some(metal), _) => metal,
+        (None, Some(hcm)) if hcm >= 230 => 255,
+        _ => 0,
+    }
+}
+
+pub fn encode_roughness(rough: Option<u8>, smooth: Option<u8>) -> u8 {
+    match (rough, smooth) {
+        (Some(rough), _) => rough,
+        (None, Some(smooth)) => 255 - smooth,
+        (None, None) => 255,
+    }
+}
+
+// Java keeps textures in `assets/<namespace>/textures/block`, Bedrock in `textures/blocks`
+pub fn texture_dir(rel_dir: &Path) -> PathBuf {
+    let components: Vec<String> = rel_dir
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    let start = components.iter().position(|c| c == "textures").unwrap_or(0);
+
+    components[start..]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| match (i, c.as_str()) {
+            (1, "block") => "blocks",
+            (1, "item") => "items",
+            _ => c.as_str(),
+        })
+        .collect()
+}
+
+fn mer(material: &Material, subsurface: bool) -> RgbaImage {
+    RgbaImage::from_fn(material.width, material.height, |x, y| {
+        image::Rgba([
+            encode_metalness(sample(&material.metal, x, y), sample(&material.hcm, x, y)),
+            sample(&material.emissive, x, y).unwrap_or(0),
+            encode_roughness(
+                sample(&material.rough, x, y),
+                sample(&material.smooth, x, y),
+            ),
+            if subsurface {
+                sample(&material.sss, x, y).unwrap_or(0)
+            } else {
+                255
+            },
+        ])
+    })
+}
+
+pub fn texture_set(name: &str, subsurface: bool, depth: Option<(&str, String)>) -> String {
+    let (version, mer_key) = if subsurface {
+        (
+            TEXTURE_SET_VERSION_MERS,
+            "metalness_emissive_roughness_subsurface",
+        )
+    } else {
+        (TEXTURE_SET_VERSION, "metalness_emissive_roughness")
+    };
+
+    let mut set = json!({
+        "color": name,
+        mer_key: format!("{}_mer", name),
+    });
+    if let Some((key, file)) = depth {
+        set[key] = json!(file);
+    }
+
+    let texture_set = json!({
+        "format_version": version,
+        "minecraft:texture_set": set,
+    });
+
+    serde_json::to_string_pretty(&texture_set).unwrap_or_default()
+}
+
+// Encodes a material into `dir`, returning each zip entry with its bytes
+pub fn encode_files(material: &Material, dir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let name = &material.name;
+    let subsurface = material.sss.is_some();
+    let entry = |file_name: String| zip_path(&dir.join(file_name));
+
+    let mut files = Vec::new();
+
+    if let Some(color) = material.color_with_opacity() {
+        files.push((entry(format!("{}.png", name)), png_bytes(&color)?));
+    }
+
+    files.push((
+        entry(format!("{}_mer.png", name)),
+        png_bytes(&mer(material, subsurface))?,
+    ));
+
+    // A texture set takes either a normal map or a height map, never both
+    let depth = if let Some(normal) = &material.normal {
+        let normal = image::DynamicImage::ImageRgb8(normal.clone()).to_rgba8();
+        files.push((entry(format!("{}_normal.png", name)), png_bytes(&normal)?));
+        Some(("normal", format!("{}_normal", name)))
+    } else if let Some(height) = &material.heightmap {
+        let height = image::DynamicImage::ImageLuma8(height.clone()).to_rgba8();
+        files.push((
+            entry(format!("{}_heightmap.png", name)),
+            png_bytes(&height)?,
+        ));
+        Some(("heightmap", format!("{}_heightmap", name)))
+    } else {
+        None
+    };
+
+    files.push((
+        entry(format!("{}.texture_set.json", name)),
+        texture_set(name, subsurface, depth).into_bytes(),
+    ));
+
+    Ok(files)
+}
+
+// Manifests from format 2 on declare the engine version the pack needs
+const MIN_ENGINE_FORMAT: i8 = 2;
+
+// A texture set's `format_version` as numbers, e.g. `1.21.30` -> [1, 21, 30]
+fn parse_version(version: &str) -> Option<[u32; 3]> {
+    let mut parts = version.split('.').map(|part| part.parse().ok());
+    Some([parts.next()??, parts.next()??, parts.next()??])
+}
+
+// The oldest engine that reads every texture set in `files`, which is the
+// newest texture set format among them
+pub fn min_engine_version<'a>(files: impl IntoIterator<Item = &'a (String, Vec<u8>)>) -> [u32; 3] {
+    files
+        .into_iter()
+        .filter(|(name, _)| name.ends_with(".texture_set.json"))
+        .filter_map(|(_, bytes)| {
+            let texture_set: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+            parse_version(texture_set["format_version"].as_str()?)
+        })
+        .max()
+        .unwrap_or_else(|| parse_version(TEXTURE_SET_VERSION).unwrap_or_default())
+}
+
+// Bedrock only replaces an installed pack when the version goes up, so every
+// build gets a newer one: 1.<date>.<time>, e.g. 1.20261018.113319
+pub fn build_version(now: chrono::DateTime<chrono::Utc>) -> [u32; 3] {
+    let date = now.format("%Y%m%d").to_string().parse().unwrap_or(0);
+    let time = now.format("%H%M%S").to_string().parse().unwrap_or(0);
+    [1, date, time]
+}
+
+pub fn manifest(
+    project_yml: &ProjectYml,
+    profile: &Profile,
+    version: [u32; 3],
+    min_engine_version: [u32; 3],
+) -> String {
+    // The UUIDs stay the same between builds and the version goes up, so
+    // Bedrock treats a new build as an update of the same pack
+    let seed = format!("{}/{}", project_yml.name, profile.name);
+    let header_uuid = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, seed.as_bytes());
+    let module_uuid = uuid::Uuid::new_v5(&header_uuid, b"resources");
+
+    let description = profile
+        .description
+        .clone()
+        .or_else(|| project_yml.description.clone())
+        .unwrap_or_default();
+
+    let mut manifest = json!({
+        "format_version": profile.format,
+        "header": {
+            "name": project_yml.name,
+            "description": description,
+            "uuid": header_uuid.to_string(),
+            "version": version,
+        },
+        "modules": [{
+            "type": "resources",
+            "uuid": module_uuid.to_string(),
+            "version": version,
+        }],
+        "capabilities": ["raytraced"],
+    });
+    if profile.format >= MIN_ENGINE_FORMAT {
+        manifest["header"]["min_engine_version"] = json!(min_engine_version);
+    }
+
+    serde_json::to_string_pretty(&manifest).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::project::structs::{GameEdition, InputFormat};
+    use chrono::TimeZone;
+    use image::{GrayImage, Luma, Rgb, RgbImage};
+
+    fn material(sss: Option<u8>, normal: bool) -> Material {
+        let gray = |value: u8| Some(GrayImage::from_pixel(1, 1, Luma([value])));
+        Material {
+            name: "ore".to_string(),
+            width: 1,
+            height: 1,
+            metal: gray(200),
+            emissive: gray(50),
+            rough: gray(30),
+            sss: sss.and_then(gray),
+            normal: normal.then(|| RgbImage::from_pixel(1, 1, Rgb([128, 128, 255]))),
+            heightmap: gray(90),
+            ..Default::default()
+        }
+    }
+
+    fn files(material: &Material) -> Vec<(String, Vec<u8>)> {
+        encode_files(material, Path::new("textures/blocks")).unwrap()
+    }
+
+    fn file<'a>(files: &'a [(String, Vec<u8>)], name: &str) -> &'a [u8] {
+        &files.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    fn mer_pixel(files: &[(String, Vec<u8>)]) -> [u8; 4] {
+        let mer = file(files, "textures/blocks/ore_mer.png");
+        image::load_from_memory(mer)
+            .unwrap()
+            .to_rgba8()
+            .get_pixel(0, 0)
+            .0
+    }
+
+    fn texture_set_json(files: &[(String, Vec<u8>)]) -> serde_json::Value {
+        serde_json::from_slice(file(files, "textures/blocks/ore.texture_set.json")).unwrap()
+    }
+
+    #[test]
+    fn mer_texture_set() {
+        let files = files(&material(None, true));
+        // Without subsurface scattering the alpha is left opaque
+        assert_eq!(mer_pixel(&files), [200, 50, 30, 255]);
+        assert_eq!(
+            texture_set_json(&files),
+            json!({
+                "format_version": "1.16.100",
+                "minecraft:texture_set": {
+                    "color": "ore",
+                    "metalness_emissive_roughness": "ore_mer",
+                    "normal": "ore_normal",
+                },
+            })
+        );
+        assert!(files
+            .iter()
+            .all(|(name, _)| name != "textures/blocks/ore_heightmap.png"));
+    }
+
+    #[test]
+    fn mers_texture_set() {
+        let files = files(&material(Some(77), false));
+        assert_eq!(mer_pixel(&files), [200, 50, 30, 77]);
+        assert_eq!(
+            texture_set_json(&files),
+            json!({
+                "format_version": "1.21.30",
+                "minecraft:texture_set": {
+                    "color": "ore",
+                    "metalness_emissive_roughness_subsurface": "ore_mer",
+                    "heightmap": "ore_heightmap",
+                },
+            })
+        );
+    }
+
+    fn profile(format: i8) -> Profile {
+        Profile {
+            edition: GameEdition::Bedrock,
+            format,
+            name: "rtx".to_string(),
+            description: None,
+            block_texture_size: None,
+            item_texture_size: None,
+            texture_scale: None,
+            auto_level_height: None,
+            bake_occlusion_texture: None,
+            output_format: None,
+        }
+    }
+
+    #[test]
+    fn manifest_versions() {
+        let project_yml = ProjectYml::new("Test".to_string(), None, InputFormat::Raw);
+        let mers = files(&material(Some(77), false));
+        let mer = files(&material(None, false));
+        assert_eq!(min_engine_version(&mers), [1, 21, 30]);
+        assert_eq!(min_engine_version(mer.iter().chain(&mers)), [1, 21, 30]);
+        assert_eq!(min_engine_version(&mer), [1, 16, 100]);
+
+        let build = |profile: &Profile, version| -> serde_json::Value {
+            serde_json::from_str(&manifest(&project_yml, profile, version, [1, 21, 30])).unwrap()
+        };
+        let first = build(&profile(2), [1, 20261018, 90000]);
+        let second = build(&profile(2), [1, 20261018, 90100]);
+
+        assert_eq!(first["header"]["uuid"], second["header"]["uuid"]);
+        assert_eq!(first["modules"][0]["uuid"], second["modules"][0]["uuid"]);
+        assert_eq!(second["header"]["version"], json!([1, 20261018, 90100]));
+        assert_eq!(second["modules"][0]["version"], json!([1, 20261018, 90100]));
+        assert_eq!(first["header"]["min_engine_version"], json!([1, 21, 30]));
+
+        // Format 1 manifests have no min_engine_version
+        let old = build(&profile(1), [1, 0, 0]);
+        assert!(old["header"].get("min_engine_version").is_none());
+    }
+
+    #[test]
+    fn build_versions_go_up() {
+        let version =
+            |h, m, s| build_version(chrono::Utc.with_ymd_and_hms(2026, 10, 18, h, m, s).unwrap());
+        assert_eq!(version(9, 5, 7), [1, 20261018, 90507]);
+        assert!(version(9, 5, 7) < version(9, 5, 8));
+        assert!(
+            version(23, 59, 59)
+                < build_version(chrono::Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap())
+        );
+    }
+}
@@ -0,0 +1,160 @@
+// Packs a material into LabPBR 1.3 textures, the per-channel rules live in
+// `codec::labpbr`
+
+use anyhow::Result;
+use image::RgbaImage;
+use std::{fs, path::Path};
+
+use super::{sample, Material};
+use crate::core::codec::labpbr::{
+    encode_emissive, encode_f0, encode_height, encode_porosity_sss, encode_smoothness,
+};
+
+pub struct LabPbrTextures {
+    pub color: Option<RgbaImage>,
+    pub normal: Option<RgbaImage>,
+    pub specular: Option<RgbaImage>,
+}
+
+pub fn encode(material: &Material) -> LabPbrTextures {
+    let (width, height) = (material.width, material.height);
+
+    let color = material.color_with_opacity();
+
+    let has_normal =
+        material.normal.is_some() || material.heightmap.is_some() || material.occlusion.is_some();
+
+    let normal = has_normal.then(|| {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let (nx, ny) = material
+                .normal
+                .as_ref()
+                .map_or((128, 128), |n| (n.get_pixel(x, y)[0], n.get_pixel(x, y)[1]));
+            let ao = sample(&material.occlusion, x, y).unwrap_or(255);
+            let height = sample(&material.heightmap, x, y).map_or(255, encode_height);
+
+            image::Rgba([nx, ny, ao, height])
+        })
+    });
+
+    let has_specular = [
+        &material.smooth,
+        &material.rough,
+        &material.metal,
+        &material.hcm,
+        &material.f0,
+        &material.porosity,
+        &material.sss,
+        &material.emissive,
+    ]
+    .iter()
+    .any(|channel| channel.is_some());
+
+    let specular = has_specular.then(|| {
+        RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([
+                encode_smoothness(
+                    sample(&material.smooth, x, y),
+                    sample(&material.rough, x, y),
+                ),
+                encode_f0(
+                    sample(&material.f0, x, y),
+                    sample(&material.hcm, x, y),
+                    sample(&material.metal, x, y),
+                ),
+                encode_porosity_sss(
+                    sample(&material.porosity, x, y),
+                    sample(&material.sss, x, y),
+                ),
+                encode_emissive(sample(&material.emissive, x, y)),
+            ])
+        })
+    });
+
+    LabPbrTextures {
+        color,
+        normal,
+        specular,
+    }
+}
+
+// The textures to write for a material, keyed by file name
+pub fn encode_files(material: &Material) -> Vec<(String, RgbaImage)> {
+    let textures = encode(material);
+
+    [
+        (textures.color, format!("{}.png", material.name)),
+        (textures.normal, format!("{}_n.png", material.name)),
+        (textures.specular, format!("{}_s.png", material.name)),
+    ]
+    .into_iter()
+    .filter_map(|(img, file_name)| img.map(|img| (file_name, img)))
+    .collect()
+}
+
+// Writes `<name>.png`, `<name>_n.png` and `<name>_s.png` into `dest` and
+// returns how many files were written
+pub fn write_material(material: &Material, dest: &Path) -> Result<usize> {
+    fs::create_dir_all(dest)?;
+
+    let files = encode_files(material);
+    for (file_name, img) in &files {
+        img.save(dest.join(file_name))?;
+    }
+
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{export::load_material, project::structs::InputFormat};
+    use image::{GrayImage, Luma, Rgb, RgbImage};
+
+    #[test]
+    fn material_folder_to_labpbr() {
+        let material_dir = tempfile::tempdir().unwrap();
+        let dir = material_dir.path().join("stone");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("mat.yml"), "").unwrap();
+
+        let gray = |name: &str, value: u8| {
+            GrayImage::from_pixel(2, 2, Luma([value]))
+                .save(dir.join(name))
+                .unwrap()
+        };
+        RgbImage::from_pixel(2, 2, Rgb([10, 20, 30]))
+            .save(dir.join("color.png"))
+            .unwrap();
+        RgbImage::from_pixel(2, 2, Rgb([128, 128, 255]))
+            .save(dir.join("normal.png"))
+            .unwrap();
+        gray("opacity.png", 200);
+        gray("occlusion.png", 40);
+        gray("height.png", 0);
+        gray("smooth.png", 90);
+        gray("f0.png", 20);
+        gray("sss.png", 255);
+
+        let out = tempfile::tempdir().unwrap();
+        let material = load_material(&dir, InputFormat::Raw).unwrap();
+        assert_eq!(write_material(&material, out.path()).unwrap(), 3);
+        let pixel = |file_name: &str| {
+            image::open(out.path().join(file_name))
+                .unwrap()
+                .to_rgba8()
+                .get_pixel(1, 1)
+                .0
+        };
+
+        // Opacity goes into the colour's alpha
+        assert_eq!(pixel("stone.png"), [10, 20, 30, 200]);
+        // X/Y pass through the normal filter, AO goes in blue and the height
+        // in alpha, never 0
+        let [nx, ny, ao, height] = pixel("stone_n.png");
+        assert!(nx.abs_diff(128) <= 1 && ny.abs_diff(128) <= 1);
+        assert_eq!((ao, height), (40, 1));
+        // No emissive.png is 255, "no emission"
+        assert_eq!(pixel("stone_s.png"), [90, 20, 255, 255]);
+    }
+}
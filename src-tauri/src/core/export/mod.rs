@@ -0,0 +1,499 @@
+pub mod bedrock;
+pub mod labpbr;
+pub mod oldpbr;
+pub mod publish;
+
+use anyhow::{anyhow, Result};
+use image::{
+    imageops::FilterType, DynamicImage, GrayImage, ImageBuffer, ImageFormat, RgbImage, RgbaImage,
+};
+use rayon::prelude::*;
+use serde_json::json;
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    codec::{decode_packed, find_source_file},
+    error::{CommandResult, Error},
+    events::EventSink,
+    home::{get_projects_vec, project_not_found},
+    interface::{
+        get_texture_properties_grayscale, load_mat_yml,
+        normal::{apply_curved_normals, generate_normal_map},
+        prepare_height, process_pixels_color, process_pixels_grayscale_common,
+        structs::{MatYml, TEXTURE_FILES},
+    },
+    metals::metal_coverage,
+    normal_map::KernelSize,
+    project::{load_project_yml, structs::InputFormat},
+};
+
+// load_mat_yml only reads mat.yml, so only it makes a folder exportable
+pub const MAT_FILES: [&str; 1] = ["mat.yml"];
+
+// Every channel of a material folder, resized to the same dimensions and with
+// the mat.yml overrides already applied. Missing channels are left as None so
+// the encoders can fall back to the spec defaults.
+#[derive(Default)]
+pub struct Material {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub color: Option<RgbaImage>,
+    pub opacity: Option<GrayImage>,
+    pub heightmap: Option<GrayImage>,
+    pub normal: Option<RgbImage>,
+    pub occlusion: Option<GrayImage>,
+    pub smooth: Option<GrayImage>,
+    pub rough: Option<GrayImage>,
+    pub metal: Option<GrayImage>,
+    pub hcm: Option<GrayImage>,
+    pub f0: Option<GrayImage>,
+    pub porosity: Option<GrayImage>,
+    pub sss: Option<GrayImage>,
+    pub emissive: Option<GrayImage>,
+}
+
+impl Material {
+    // Scales every channel to the new size. Pixel art is upscaled with nearest
+    // neighbour so it stays crisp, downscaling averages the pixels instead.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) || width == 0 || height == 0 {
+            return;
+        }
+
+        let filter = if width > self.width {
+            FilterType::Nearest
+        } else {
+            FilterType::Triangle
+        };
+
+        fn scale<P: image::Pixel + 'static>(
+            img: &mut Option<ImageBuffer<P, Vec<P::Subpixel>>>,
+            width: u32,
+            height: u32,
+            filter: FilterType,
+        ) {
+            if let Some(buf) = img {
+                *buf = image::imageops::resize(buf, width, height, filter);
+            }
+        }
+
+        scale(&mut self.color, width, height, filter);
+        scale(&mut self.normal, width, height, filter);
+        for channel in [
+            &mut self.opacity,
+            &mut self.heightmap,
+            &mut self.occlusion,
+            &mut self.smooth,
+            &mut self.rough,
+            &mut self.metal,
+            &mut self.hcm,
+            &mut self.f0,
+            &mut self.porosity,
+            &mut self.sss,
+            &mut self.emissive,
+        ] {
+            scale(channel, width, height, filter);
+        }
+
+        self.width = width;
+        self.height = height;
+    }
+
+    // The colour texture with the opacity channel written into its alpha
+    pub fn color_with_opacity(&self) -> Option<RgbaImage> {
+        self.color.as_ref().map(|color| {
+            let mut color = color.clone();
+            if let Some(opacity) = &self.opacity {
+                for (pixel, alpha) in color.pixels_mut().zip(opacity.pixels()) {
+                    pixel[3] = alpha[0];
+                }
+            }
+            color
+        })
+    }
+}
+
+// One pixel of a grayscale channel, None when the material doesn't have it
+pub fn sample(img: &Option<GrayImage>, x: u32, y: u32) -> Option<u8> {
+    img.as_ref().map(|img| img.get_pixel(x, y)[0])
+}
+
+#[derive(serde::Serialize, Default)]
+pub struct ExportSummary {
+    pub materials: usize,
+    pub files: usize,
+    pub failed: Vec<ExportFailure>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ExportFailure {
+    pub path: String,
+    pub error: String,
+}
+
+pub fn is_material_dir(path: &Path) -> bool {
+    MAT_FILES.iter().any(|&file| path.join(file).exists())
+}
+
+// Walks the project and returns every folder that holds a mat.yml. Material
+// folders are leaves, so their children are never visited.
+pub fn find_material_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        if is_material_dir(&dir) {
+            dirs.push(dir);
+            continue;
+        }
+
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.filter_map(Result::ok) {
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path);
+                }
+            }
+        }
+    }
+
+    dirs.sort();
+    dirs
+}
+
+fn texture_pattern(name: &str) -> &'static str {
+    TEXTURE_FILES
+        .iter()
+        .find(|t| t.name == name)
+        .map(|t| t.pattern)
+        .unwrap_or(r"^$")
+}
+
+fn find_texture(material_dir: &Path, name: &str, format: InputFormat) -> Option<PathBuf> {
+    find_source_file(material_dir, texture_pattern(name), format)
+}
+
+fn fit<P: image::Pixel + 'static>(
+    img: ImageBuffer<P, Vec<P::Subpixel>>,
+    width: u32,
+    height: u32,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    if img.dimensions() == (width, height) {
+        img
+    } else {
+        image::imageops::resize(&img, width, height, FilterType::Nearest)
+    }
+}
+
+fn load_grayscale(
+    source: Option<GrayImage>,
+    name: &str,
+    mat_yml: &MatYml,
+    width: u32,
+    height: u32,
+) -> Result<Option<GrayImage>> {
+    let source = source.map(|img| fit(img, width, height));
+
+    let properties = get_texture_properties_grayscale(name, mat_yml);
+    let overrides_value = properties
+        .as_ref()
+        .is_some_and(|p| p.value.unwrap_or(0.0) > 0.0);
+
+    let img = match (source, properties) {
+        (Some(img), Some(properties)) => {
+            let buf = process_pixels_grayscale_common(
+                img.as_raw(),
+                width as usize,
+                height as usize,
+                &properties,
+            );
+            GrayImage::from_raw(width, height, buf)
+        }
+        // A value override fills the channel even when there is no file for it
+        (None, Some(properties)) if overrides_value => {
+            let buf = process_pixels_grayscale_common(
+                &vec![0; (width * height) as usize],
+                width as usize,
+                height as usize,
+                &properties,
+            );
+            GrayImage::from_raw(width, height, buf)
+        }
+        (img, _) => img,
+    };
+
+    Ok(img)
+}
+
+fn load_color(
+    source: Option<RgbaImage>,
+    mat_yml: &MatYml,
+    width: u32,
+    height: u32,
+) -> Option<RgbaImage> {
+    let Some(color) = &mat_yml.color else {
+        return source;
+    };
+
+    let img = match source {
+        Some(img) => img,
+        // Like the grayscale value, an override fills the channel even when
+        // there is no file for it
+        None if color.overrides().iter().any(Option::is_some) => {
+            RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]))
+        }
+        None => return None,
+    };
+
+    let (width, height) = img.dimensions();
+    let buf = process_pixels_color(img.as_raw(), 4, color);
+    RgbaImage::from_raw(width, height, buf)
+}
+
+fn load_normal(
+    material_dir: &Path,
+    format: InputFormat,
+    packed: Option<&DynamicImage>,
+    heightmap: Option<&DynamicImage>,
+    mat_yml: &MatYml,
+    width: u32,
+    height: u32,
+) -> Result<Option<RgbImage>> {
+    let normal = mat_yml.normal.clone().unwrap_or_default();
+    let curve_x = normal.curve_x.unwrap_or(0.0);
+    let curve_y = normal.curve_y.unwrap_or(0.0);
+    let radius_x = normal.radius_size_x.unwrap_or(0.5);
+    let radius_y = normal.radius_size_y.unwrap_or(0.5);
+
+    let source = match find_texture(material_dir, "normal", format) {
+        Some(file) => Some(image::open(file)?.to_rgb8()),
+        None => packed.map(|img| img.to_rgb8()),
+    };
+
+    if let Some(img) = source {
+        let img = fit(img, width, height);
+        let mut buf = img.into_raw();
+        let buf = apply_curved_normals(
+            &mut buf,
+            width as usize,
+            height as usize,
+            curve_x,
+            curve_y,
+            radius_x,
+            radius_y,
+        );
+        return Ok(RgbImage::from_raw(width, height, buf));
+    }
+
+    // No normal map in the folder, so derive one from the height map
+    if let Some(heightmap) = heightmap {
+        let generated = generate_normal_map(
+            heightmap,
+            KernelSize::from_method(normal.method),
+            normal.strength.unwrap_or(1.0),
+            curve_x,
+            curve_y,
+            radius_x,
+            radius_y,
+        );
+        let img = RgbImage::from_raw(
+            generated.info.width as u32,
+            generated.info.height as u32,
+            generated.buf,
+        )
+        .ok_or_else(|| anyhow!("Generated normal map has an invalid size"))?;
+
+        return Ok(Some(fit(img, width, height)));
+    }
+
+    Ok(None)
+}
+
+// Separate channel files always win over the ones decoded from packed textures
+pub fn load_material(material_dir: &Path, format: InputFormat) -> Result<Material> {
+    let name = material_dir
+        .file_name()
+        .ok_or_else(|| anyhow!("Material folder has no name"))?
+        .to_string_lossy()
+        .to_string();
+    let mat_yml = load_mat_yml(material_dir)?;
+    let packed = decode_packed(material_dir, format);
+
+    let color = match find_texture(material_dir, "color", format) {
+        Some(file) => Some(image::open(file)?.to_rgba8()),
+        None => packed.get("color").map(|img| img.to_rgba8()),
+    };
+
+    // The colour texture decides the size, otherwise the first channel found does
+    let (width, height) = match &color {
+        Some(img) => img.dimensions(),
+        None => TEXTURE_FILES
+            .iter()
+            .filter_map(|t| find_texture(material_dir, t.name, format))
+            .find_map(|file| image::image_dimensions(file).ok())
+            .or_else(|| {
+                packed
+                    .values()
+                    .next()
+                    .map(|img| (img.width(), img.height()))
+            })
+            .unwrap_or((16, 16)),
+    };
+
+    let color = load_color(color, &mat_yml, width, height);
+
+    // Normals are generated from the adjusted height, so both stay in step
+    let heightmap = match find_texture(material_dir, "height", format) {
+        Some(file) => Some(image::open(file)?),
+        None => packed.get("height").cloned(),
+    };
+    let heightmap = prepare_height(heightmap, &mat_yml, (width, height));
+
+    let gray = |name: &str| {
+        let source = match find_texture(material_dir, name, format) {
+            Some(file) => Some(image::open(file)?.to_luma8()),
+            None => packed.get(name).map(|img| img.to_luma8()),
+        };
+        load_grayscale(source, name, &mat_yml, width, height)
+    };
+
+    // A metal picked in mat.yml is written over hcm.png
+    let (hcm, f0) = match metal_coverage(material_dir, &mat_yml, width, height)? {
+        Some(coverage) => (
+            Some(coverage.apply_hcm(gray("hcm")?, width, height)),
+            coverage.apply_f0(gray("f0")?),
+        ),
+        None => (gray("hcm")?, gray("f0")?),
+    };
+
+    Ok(Material {
+        name,
+        width,
+        height,
+        color,
+        opacity: gray("opacity")?,
+        normal: load_normal(
+            material_dir,
+            format,
+            packed.get("normal"),
+            heightmap.as_ref(),
+            &mat_yml,
+            width,
+            height,
+        )?,
+        heightmap: heightmap.map(|img| fit(img.to_luma8(), width, height)),
+        occlusion: gray("occlusion")?,
+        smooth: gray("smooth")?,
+        rough: gray("rough")?,
+        metal: gray("metal")?,
+        hcm,
+        f0,
+        porosity: gray("porosity")?,
+        sss: gray("sss")?,
+        emissive: gray("emissive")?,
+    })
+}
+
+pub fn png_bytes(img: &RgbaImage) -> Result<Vec<u8>> {
+    let mut buf = Cursor::new(Vec::new());
+    img.write_to(&mut buf, ImageFormat::Png)?;
+    Ok(buf.into_inner())
+}
+
+// Zip entries always use forward slashes, whatever the platform
+pub fn zip_path(rel_path: &Path) -> String {
+    rel_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Where a material's textures end up in the output, e.g.
+// `assets/minecraft/textures/block/stone/` -> `<out>/assets/minecraft/textures/block/`
+pub fn output_dir(project_dir: &Path, material_dir: &Path, out_dir: &Path) -> PathBuf {
+    let relative = material_dir
+        .strip_prefix(project_dir)
+        .ok()
+        .and_then(|p| p.parent())
+        .unwrap_or(Path::new(""));
+
+    out_dir.join(relative)
+}
+
+pub fn export_labpbr(
+    project_dir: &Path,
+    out_dir: &Path,
+    sink: &dyn EventSink,
+) -> Result<ExportSummary> {
+    let material_dirs = find_material_dirs(project_dir);
+    let format = load_project_yml(project_dir)
+        .map(|p| p.input.format)
+        .unwrap_or_default();
+
+    let results: Vec<(PathBuf, Result<usize>)> = material_dirs
+        .par_iter()
+        .map(|material_dir| {
+            sink.emit(
+                "export-progress",
+                json!(material_dir.to_string_lossy().to_string()),
+            );
+
+            let result = load_material(material_dir, format).and_then(|material| {
+                let dest = output_dir(project_dir, material_dir, out_dir);
+                labpbr::write_material(&material, &dest)
+            });
+
+            (material_dir.clone(), result)
+        })
+        .collect();
+
+    let mut summary = ExportSummary::default();
+    for (material_dir, result) in results {
+        match result {
+            Ok(files) => {
+                summary.materials += 1;
+                summary.files += files;
+            }
+            Err(e) => {
+                eprintln!("Failed to export {}: {}", material_dir.display(), e);
+                summary.failed.push(ExportFailure {
+                    path: material_dir.to_string_lossy().to_string(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+pub fn get_project_path(project_id: &str, sink: &dyn EventSink) -> CommandResult<PathBuf> {
+    get_projects_vec(sink)
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .map(|p| PathBuf::from(p.path))
+        .ok_or_else(|| project_not_found(project_id, sink))
+}
+
+#[tauri::command]
+pub fn export_labpbr_textures(
+    project_id: String,
+    out_dir: String,
+    app: tauri::AppHandle,
+) -> CommandResult<String> {
+    let project_path = get_project_path(&project_id, &app)?;
+
+    let out_path = Path::new(&out_dir);
+    fs::create_dir_all(out_path).map_err(|e| Error::io(out_path, e))?;
+
+    let summary = export_labpbr(&project_path, out_path, &app)
+        .map_err(|e| Error::other(out_path, format!("Failed to export textures: {}", e)))?;
+
+    serde_json::to_string(&summary).map_err(|e| Error::other(out_path, e))
+}
@@ -0,0 +1,79 @@
+// Packs a material into OldPBR / SEUS textures, the per-channel rules live in
+// `codec::oldpbr`
+
+use image::RgbaImage;
+
+use super::{sample, Material};
+use crate::core::codec::{
+    labpbr::{encode_height, normal_z},
+    oldpbr::{encode_metal, encode_specular_pixel},
+};
+
+// OldPBR has no AO channel, the normal keeps its Z in blue
+fn encode_normal(material: &Material) -> Option<RgbaImage> {
+    if material.normal.is_none() && material.heightmap.is_none() {
+        return None;
+    }
+
+    Some(RgbaImage::from_fn(
+        material.width,
+        material.height,
+        |x, y| {
+            let [nx, ny, nz] = material
+                .normal
+                .as_ref()
+                .map_or_else(|| [128, 128, normal_z(128, 128)], |n| n.get_pixel(x, y).0);
+            let height = sample(&material.heightmap, x, y).map_or(255, encode_height);
+
+            image::Rgba([nx, ny, nz, height])
+        },
+    ))
+}
+
+fn encode_specular(material: &Material) -> Option<RgbaImage> {
+    let has_specular = [
+        &material.smooth,
+        &material.rough,
+        &material.metal,
+        &material.hcm,
+        &material.f0,
+        &material.emissive,
+    ]
+    .iter()
+    .any(|channel| channel.is_some());
+
+    has_specular.then(|| {
+        RgbaImage::from_fn(material.width, material.height, |x, y| {
+            let metal = encode_metal(
+                sample(&material.metal, x, y),
+                sample(&material.f0, x, y),
+                sample(&material.hcm, x, y),
+            );
+
+            image::Rgba(encode_specular_pixel(
+                sample(&material.smooth, x, y),
+                sample(&material.rough, x, y),
+                metal,
+                sample(&material.emissive, x, y),
+            ))
+        })
+    })
+}
+
+// The textures to write for a material, keyed by file name
+pub fn encode_files(material: &Material) -> Vec<(String, RgbaImage)> {
+    [
+        (
+            material.color_with_opacity(),
+            format!("{}.png", material.name),
+        ),
+        (encode_normal(material), format!("{}_n.png", material.name)),
+        (
+            encode_specular(material),
+            format!("{}_s.png", material.name),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(img, file_name)| img.map(|img| (file_name, img)))
+    .collect()
+}
@@ -0,0 +1,462 @@
+use anyhow::Result;
+use image::{GrayImage, RgbaImage};
+use rayon::prelude::*;
+use serde_json::json;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use super::{
+    bedrock, find_material_dirs, get_project_path, is_material_dir, labpbr, load_material, oldpbr,
+    png_bytes, zip_path, ExportFailure, ExportSummary, Material,
+};
+use crate::core::error::{CommandResult, Error};
+use crate::core::events::EventSink;
+use crate::core::project::{
+    load_project_yml,
+    profiles::validate_profile,
+    structs::{GameEdition, InputFormat, OutputFormat, Profile, ProjectYml},
+};
+use crate::core::template::TEMPLATES_DIR;
+
+// Files in the project root that are replaced or never shipped
+const SKIPPED_FILES: [&str; 2] = ["project.yml", "pack.mcmeta"];
+
+// Zip entry path and encoded png bytes
+type EncodedFiles = Vec<(String, Vec<u8>)>;
+
+// The profile called `profile_name`, checked the same way the editor checks
+// profiles so a hand-edited project.yml can't publish what it would refuse
+pub fn find_profile(
+    project_dir: &Path,
+    project_yml: &ProjectYml,
+    profile_name: &str,
+) -> CommandResult<Profile> {
+    let project_yml_path = project_dir.join("project.yml");
+    let (index, profile) = project_yml
+        .profiles
+        .iter()
+        .flatten()
+        .enumerate()
+        .find(|(_, p)| p.name == profile_name)
+        .ok_or_else(|| {
+            Error::not_found(
+                &project_yml_path,
+                format!("Profile {} not found", profile_name),
+            )
+        })?;
+
+    validate_profile(profile).map_err(|(field, message)| {
+        Error::invalid_field(
+            &project_yml_path,
+            format!("profiles[{}].{}", index, field),
+            message,
+        )
+    })?;
+    Ok(profile.clone())
+}
+
+fn is_texture_dir(rel_dir: &Path, kind: &str) -> bool {
+    let components: Vec<_> = rel_dir.components().map(|c| c.as_os_str()).collect();
+    components
+        .windows(2)
+        .any(|pair| pair[0] == "textures" && pair[1] == kind)
+}
+
+// Block and item textures are resized to the profile's fixed width, anything
+// else is multiplied by the texture scale. The height follows the width so
+// animation strips keep their frame count.
+fn target_size(material: &Material, rel_dir: &Path, profile: &Profile) -> Option<(u32, u32)> {
+    let width = match (profile.block_texture_size, profile.item_texture_size) {
+        (Some(size), _) if is_texture_dir(rel_dir, "block") => size as u32,
+        (_, Some(size)) if is_texture_dir(rel_dir, "item") => size as u32,
+        _ => material.width * profile.texture_scale.filter(|s| *s > 0)? as u32,
+    };
+
+    if width == 0 || material.width == 0 {
+        return None;
+    }
+
+    Some((width, material.height * width / material.width))
+}
+
+// Stretches the height map so its lowest point is 0 and its highest 255, a
+// flat map is left as it is
+fn auto_level_height(height: &mut GrayImage) {
+    let min = height.pixels().map(|p| p[0]).min().unwrap_or(0);
+    let max = height.pixels().map(|p| p[0]).max().unwrap_or(255);
+    if min == max || (min, max) == (0, 255) {
+        return;
+    }
+
+    let range = (max - min) as u32;
+    for pixel in height.pixels_mut() {
+        pixel[0] = (((pixel[0] - min) as u32 * 255 + range / 2) / range) as u8;
+    }
+}
+
+// Multiplies the colour by the occlusion for shaders that don't read AO
+fn bake_occlusion(color: &mut RgbaImage, occlusion: &GrayImage) {
+    for (pixel, ao) in color.pixels_mut().zip(occlusion.pixels()) {
+        for channel in 0..3 {
+            pixel[channel] = ((pixel[channel] as u32 * ao[0] as u32 + 127) / 255) as u8;
+        }
+    }
+}
+
+pub fn apply_profile(material: &mut Material, rel_dir: &Path, profile: &Profile) {
+    if let Some((width, height)) = target_size(material, rel_dir, profile) {
+        material.resize(width, height);
+    }
+
+    if profile.auto_level_height.unwrap_or(false) {
+        if let Some(height) = &mut material.heightmap {
+            auto_level_height(height);
+        }
+    }
+
+    if profile.bake_occlusion_texture.unwrap_or(false) {
+        if let (Some(color), Some(occlusion)) = (&mut material.color, &material.occlusion) {
+            bake_occlusion(color, occlusion);
+        }
+    }
+}
+
+pub fn pack_mcmeta(project_yml: &ProjectYml, profile: &Profile) -> String {
+    let description = profile
+        .description
+        .clone()
+        .or_else(|| project_yml.description.clone())
+        .unwrap_or_default();
+
+    let mc_meta = json!({
+        "pack": {
+            "pack_format": profile.format,
+            "description": description,
+        }
+    });
+
+    serde_json::to_string_pretty(&mc_meta).unwrap_or_default()
+}
+
+// Everything outside material folders (models, blockstates, sounds, textures
+// that were never split, ...) is shipped unchanged
+fn find_asset_files(project_dir: &Path, skip: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![project_dir.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if name.starts_with('.') || name == TEMPLATES_DIR || path == skip {
+                continue;
+            }
+
+            if path.is_dir() {
+                if !is_material_dir(&path) {
+                    stack.push(path);
+                }
+            } else if !(dir == project_dir && SKIPPED_FILES.contains(&name.as_str())) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+fn encode_material(
+    project_dir: &Path,
+    material_dir: &Path,
+    format: InputFormat,
+    profile: &Profile,
+) -> Result<EncodedFiles> {
+    let rel_dir = material_dir
+        .strip_prefix(project_dir)?
+        .parent()
+        .unwrap_or(Path::new(""));
+
+    let mut material = load_material(material_dir, format)?;
+    apply_profile(&mut material, rel_dir, profile);
+
+    let textures = match (profile.edition, profile.output_format.unwrap_or_default()) {
+        (GameEdition::Bedrock, _) => {
+            return bedrock::encode_files(&material, &bedrock::texture_dir(rel_dir))
+        }
+        (GameEdition::Java, OutputFormat::LabPbr13) => labpbr::encode_files(&material),
+        (GameEdition::Java, OutputFormat::OldPbr) => oldpbr::encode_files(&material),
+    };
+
+    textures
+        .iter()
+        .map(|(file_name, img)| Ok((zip_path(&rel_dir.join(file_name)), png_bytes(img)?)))
+        .collect()
+}
+
+pub fn publish(
+    project_dir: &Path,
+    project_yml: &ProjectYml,
+    profile: &Profile,
+    out_file: &Path,
+    sink: &dyn EventSink,
+) -> Result<ExportSummary> {
+    let material_dirs = find_material_dirs(project_dir);
+    let encoded: Vec<(PathBuf, Result<EncodedFiles>)> = material_dirs
+        .par_iter()
+        .map(|material_dir| {
+            sink.emit(
+                "export-progress",
+                json!(material_dir.to_string_lossy().to_string()),
+            );
+
+            (
+                material_dir.clone(),
+                encode_material(project_dir, material_dir, project_yml.input.format, profile),
+            )
+        })
+        .collect();
+
+    if let Some(parent) = out_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut zip = ZipWriter::new(File::create(out_file)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut summary = ExportSummary::default();
+
+    match profile.edition {
+        GameEdition::Java => {
+            zip.start_file("pack.mcmeta", options)?;
+            zip.write_all(pack_mcmeta(project_yml, profile).as_bytes())?;
+            summary.files += 1;
+
+            for file in find_asset_files(project_dir, out_file) {
+                let rel_path = file.strip_prefix(project_dir)?;
+                zip.start_file(zip_path(rel_path), options)?;
+                io::copy(&mut File::open(&file)?, &mut zip)?;
+                summary.files += 1;
+            }
+        }
+        // Java models, blockstates and sounds mean nothing to Bedrock, so only
+        // the manifest and icon go along with the textures
+        GameEdition::Bedrock => {
+            zip.start_file("manifest.json", options)?;
+            let texture_files = encoded
+                .iter()
+                .filter_map(|(_, result)| result.as_ref().ok())
+                .flatten();
+            let manifest = bedrock::manifest(
+                project_yml,
+                profile,
+                bedrock::build_version(chrono::Utc::now()),
+                bedrock::min_engine_version(texture_files),
+            );
+            zip.write_all(manifest.as_bytes())?;
+            summary.files += 1;
+
+            let pack_png = project_dir.join("pack.png");
+            if pack_png.exists() {
+                zip.start_file("pack_icon.png", options)?;
+                io::copy(&mut File::open(&pack_png)?, &mut zip)?;
+                summary.files += 1;
+            }
+        }
+    }
+
+    for (material_dir, result) in encoded {
+        match result {
+            Ok(files) => {
+                for (name, bytes) in files {
+                    zip.start_file(name, options)?;
+                    zip.write_all(&bytes)?;
+                    summary.files += 1;
+                }
+                summary.materials += 1;
+            }
+            Err(e) => {
+                eprintln!("Failed to export {}: {}", material_dir.display(), e);
+                summary.failed.push(ExportFailure {
+                    path: material_dir.to_string_lossy().to_string(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    zip.finish()?;
+
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn publish_profile(
+    project_id: String,
+    profile_name: String,
+    out_path: String,
+    app: tauri::AppHandle,
+) -> CommandResult<String> {
+    let project_path = get_project_path(&project_id, &app)?;
+    let project_yml = load_project_yml(&project_path)?;
+    let profile = find_profile(&project_path, &project_yml, &profile_name)?;
+
+    let summary = publish(
+        &project_path,
+        &project_yml,
+        &profile,
+        Path::new(&out_path),
+        &app,
+    )
+    .map_err(|e| {
+        Error::other(
+            &out_path,
+            format!("Failed to publish {}: {}", profile_name, e),
+        )
+    })?;
+
+    serde_json::to_string(&summary).map_err(|e| Error::other(&out_path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn profile(edition: GameEdition) -> Profile {
+        Profile {
+            edition,
+            format: 34,
+            name: "hd".to_string(),
+            description: None,
+            block_texture_size: Some(32),
+            item_texture_size: Some(64),
+            texture_scale: Some(2),
+            auto_level_height: None,
+            bake_occlusion_texture: None,
+            output_format: None,
+        }
+    }
+
+    fn project_yml() -> ProjectYml {
+        ProjectYml::new(
+            "Test".to_string(),
+            Some("From the project".to_string()),
+            InputFormat::Raw,
+        )
+    }
+
+    #[test]
+    fn pack_mcmeta_uses_profile_format() {
+        let mut profile = profile(GameEdition::Java);
+        let mc_meta: serde_json::Value =
+            serde_json::from_str(&pack_mcmeta(&project_yml(), &profile)).unwrap();
+        assert_eq!(mc_meta["pack"]["pack_format"], 34);
+        assert_eq!(mc_meta["pack"]["description"], "From the project");
+
+        profile.description = Some("From the profile".to_string());
+        let mc_meta: serde_json::Value =
+            serde_json::from_str(&pack_mcmeta(&project_yml(), &profile)).unwrap();
+        assert_eq!(mc_meta["pack"]["description"], "From the profile");
+    }
+
+    #[test]
+    fn target_sizes() {
+        let profile = profile(GameEdition::Java);
+        let material = Material {
+            width: 16,
+            height: 48,
+            ..Default::default()
+        };
+        let size = |rel_dir: &str| target_size(&material, Path::new(rel_dir), &profile);
+
+        // Animation strips keep their frame count
+        assert_eq!(size("assets/minecraft/textures/block"), Some((32, 96)));
+        assert_eq!(size("assets/minecraft/textures/item"), Some((64, 192)));
+        assert_eq!(size("assets/minecraft/textures/entity"), Some((32, 96)));
+        // `block` only counts right below `textures`
+        assert_eq!(size("assets/block/textures/misc"), Some((32, 96)));
+
+        let unscaled = Profile {
+            texture_scale: None,
+            ..profile.clone()
+        };
+        let other = Path::new("assets/minecraft/textures/entity");
+        assert_eq!(target_size(&material, other, &unscaled), None);
+    }
+
+    #[test]
+    fn auto_level_stretches_height() {
+        let mut height = GrayImage::from_raw(3, 1, vec![50, 100, 150]).unwrap();
+        auto_level_height(&mut height);
+        assert_eq!(height.into_raw(), [0, 128, 255]);
+
+        let mut flat = GrayImage::from_raw(2, 1, vec![80, 80]).unwrap();
+        auto_level_height(&mut flat);
+        assert_eq!(flat.into_raw(), [80, 80]);
+    }
+
+    #[test]
+    fn publish_copies_assets_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path();
+        let model = br#"{"parent": "block/cube_all"}"#;
+        let sound = [0u8, 159, 146, 150, 255];
+        for (path, contents) in [
+            ("assets/minecraft/models/block/stone.json", &model[..]),
+            ("assets/minecraft/sounds/step.ogg", &sound[..]),
+            ("pack.png", b"not really a png"),
+            ("project.yml", b"name: Test"),
+            ("pack.mcmeta", b"{}"),
+            (".hidden/notes.txt", b"private"),
+        ] {
+            let path = project_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let out_file = project_dir.join("out/pack.zip");
+        publish(
+            project_dir,
+            &project_yml(),
+            &profile(GameEdition::Java),
+            &out_file,
+            &crate::core::events::HeadlessSink::default(),
+        )
+        .unwrap();
+
+        let mut zip = zip::ZipArchive::new(File::open(&out_file).unwrap()).unwrap();
+        let mut names: Vec<String> = zip.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "assets/minecraft/models/block/stone.json",
+                "assets/minecraft/sounds/step.ogg",
+                "pack.mcmeta",
+                "pack.png",
+            ]
+        );
+
+        for (name, expected) in [
+            ("assets/minecraft/models/block/stone.json", &model[..]),
+            ("assets/minecraft/sounds/step.ogg", &sound[..]),
+            ("pack.png", b"not really a png"),
+        ] {
+            let mut contents = Vec::new();
+            zip.by_name(name)
+                .unwrap()
+                .read_to_end(&mut contents)
+                .unwrap();
+            assert_eq!(contents, expected, "{}", name);
+        }
+    }
+}
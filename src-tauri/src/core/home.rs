@@ -1,31 +1,25 @@
-use anyhow::Result;
 use serde_json::json;
-use std::fs::{self, File};
-use std::io::{Read, Write};
-use std::path::Path;
-use tauri::Emitter;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::core::image_process::{save_f0_hcm, save_porosity_sss};
 use crate::core::utils::{get_config_dir, simple_toast};
 
-use super::image_process::{save_channel_map, save_normal};
-use super::project::structs::{Input, ProjectYml};
-use super::project::update_project;
+use super::error::{CommandResult, Error};
+use super::events::EventSink;
+use super::store::{self, FileLock};
+
+use super::import::pack::{copy_and_process, unzip_and_process, ImportOptions};
+use super::migration::parse_project_yml;
+use super::project::structs::{InputFormat, ProjectYml};
 use super::utils::try_create_directory;
 
 #[tauri::command]
-pub fn get_projects(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+pub fn get_projects(app: tauri::AppHandle) -> CommandResult<serde_json::Value> {
     let config_dir = get_config_dir(&app);
     let projects_yml_path = Path::new(&config_dir).join("projects.yml");
-    if !projects_yml_path.exists() {
-        if let Err(e) = fs::write(&projects_yml_path, "") {
-            return Err(format!("Failed to create project file: {}", e));
-        }
-    }
-    let projects_content = fs::read_to_string(&projects_yml_path)
-        .map_err(|e| format!("Failed to read project file: {}", e))?;
-    let mut projects: Vec<Project> = serde_yaml::from_str(&projects_content)
-        .map_err(|e| format!("Failed to deserialize project file: {}", e))?;
+    let mut projects = load_projects(&projects_yml_path)?;
     projects.sort_by_key(|p| p.date_modified.clone());
     projects.reverse();
 
@@ -41,51 +35,78 @@ pub fn get_projects(app: tauri::AppHandle) -> Result<serde_json::Value, String>
     Ok(json!(projects))
 }
 
-pub fn get_projects_vec(app: &tauri::AppHandle) -> Vec<Project> {
-    let config_dir = get_config_dir(&app);
-    let projects_yml_path = Path::new(&config_dir).join("projects.yml");
-
-    let projects_content = match fs::read_to_string(&projects_yml_path) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Failed to read project file: {}", e);
-            return vec![];
-        }
-    };
-    let projects: Vec<Project> = match serde_yaml::from_str(&projects_content) {
-        Ok(projects) => projects,
-        Err(e) => {
-            eprintln!("Failed to deserialize project file: {}", e);
-            return vec![];
-        }
-    };
+// A missing or empty projects.yml is an empty list, a broken one is
+// recovered from its backup
+fn load_projects(projects_yml_path: &Path) -> CommandResult<Vec<Project>> {
+    match fs::read_to_string(projects_yml_path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Ok(content) if content.trim().is_empty() => return Ok(vec![]),
+        _ => (),
+    }
 
-    projects
+    store::read_yaml_or_recover(projects_yml_path)
 }
 
-#[tauri::command]
-pub fn remove_project(id: String, app: tauri::AppHandle) -> bool {
-    let config_dir = get_config_dir(&app);
-    let projects_yml_path = Path::new(&config_dir).join("projects.yml");
+// Where the project list is kept, just the file name when there's none
+pub fn projects_yml_path(sink: &dyn EventSink) -> PathBuf {
+    sink.config_dir().unwrap_or_default().join("projects.yml")
+}
 
-    let mut projects: Vec<Project> = get_projects_vec(&app);
+// A project id that isn't in projects.yml. The error points at the list, as
+// the id isn't a path.
+pub fn project_not_found(project_id: &str, sink: &dyn EventSink) -> Error {
+    Error::not_found(
+        projects_yml_path(sink),
+        format!("Project {} not found", project_id),
+    )
+}
 
-    projects.retain(|p| p.id != id);
+pub fn get_projects_vec(sink: &dyn EventSink) -> Vec<Project> {
+    // Headless runs have no project list
+    let Some(config_dir) = sink.config_dir() else {
+        return vec![];
+    };
 
-    let updated_content = match serde_yaml::to_string(&projects) {
-        Ok(content) => content,
+    match load_projects(&config_dir.join("projects.yml")) {
+        Ok(projects) => projects,
         Err(e) => {
-            eprintln!("Failed to serialize project file: {}", e);
-            return false;
+            eprintln!("Failed to read project file: {}", e);
+            vec![]
         }
-    };
-
-    if let Err(e) = fs::write(&projects_yml_path, updated_content) {
-        eprintln!("Failed to write project file: {}", e);
-        return false;
     }
+}
 
-    true
+// Runs `f` on the project list and saves the result. projects.yml stays
+// locked in between so concurrent commands don't drop each other's changes.
+pub fn modify_projects<R>(
+    sink: &dyn EventSink,
+    f: impl FnOnce(&mut Vec<Project>) -> CommandResult<R>,
+) -> CommandResult<R> {
+    let config_dir = sink
+        .config_dir()
+        .ok_or_else(|| Error::not_found("projects.yml", "There is no project list"))?;
+    fs::create_dir_all(&config_dir).map_err(|e| Error::io(&config_dir, e))?;
+    let projects_yml_path = config_dir.join("projects.yml");
+
+    let _lock = FileLock::acquire(&projects_yml_path)?;
+    let mut projects = load_projects(&projects_yml_path)?;
+    let result = f(&mut projects)?;
+    store::write_yaml(&projects_yml_path, &projects)?;
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn remove_project(id: String, app: tauri::AppHandle) -> CommandResult<()> {
+    remove_project_entry(&id, &app)
+}
+
+// Drops a project from projects.yml, its folder is left alone
+pub fn remove_project_entry(id: &str, sink: &dyn EventSink) -> CommandResult<()> {
+    modify_projects(sink, |projects| {
+        projects.retain(|p| p.id != *id);
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -97,88 +118,65 @@ pub fn create_project(
     create_realms_dirs: bool,
     create_of_dirs: bool,
     import_zip_path: Option<String>,
+    import_dir_path: Option<String>,
+    import_options: Option<ImportOptions>,
     app: tauri::AppHandle,
-) -> String {
+) -> CommandResult<String> {
     let path = Path::new(&dir_path);
+    let fail = |e: Error| {
+        simple_toast(format!("Error creating project: {}", e), &app);
+        e
+    };
 
     if !path.exists() {
-        simple_toast(
-            "Error creating project: Selected folder does not exist".to_string(),
-            app,
-        );
-        return serde_json::to_string(&CreateProject {
-            success: false,
-            id: None,
-            message: Some("Selected folder does not exist".to_string()),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string());
-    }
-
-    if path.read_dir().unwrap().count() != 0 {
-        simple_toast(
-            "Error creating project: Selected folder is not empty".to_string(),
-            app,
-        );
-        return serde_json::to_string(&CreateProject {
-            success: false,
-            id: None,
-            message: Some("Selected folder is not empty".to_string()),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string());
+        return Err(fail(Error::not_found(
+            path,
+            "Selected folder does not exist",
+        )));
     }
 
-    let config_dir = get_config_dir(&app);
-    let projects_yml_path = Path::new(&config_dir).join("projects.yml");
-
-    let mut projects: Vec<Project> = get_projects_vec(&app);
-
-    if projects.iter().any(|p| p.path == dir_path) {
-        simple_toast(
-            "Error creating project: Selected path already exists in your projects".to_string(),
-            app,
-        );
-        return serde_json::to_string(&CreateProject {
-            success: false,
-            id: None,
-            message: Some("Selected path already exists in your projects".to_string()),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string());
+    let is_empty = path
+        .read_dir()
+        .map_err(|e| fail(Error::io(path, e)))?
+        .next()
+        .is_none();
+    if !is_empty {
+        return Err(fail(Error::conflict(path, "Selected folder is not empty")));
     }
 
     let project_id = format!("{}", uuid::Uuid::new_v4());
     let project_description = description.unwrap_or("".to_string());
     let current_date = chrono::prelude::Utc::now();
 
-    projects.push(Project {
-        id: project_id.clone(),
-        path: dir_path.clone(),
-        name: name.clone(),
-        description: Some(project_description.clone()),
-        date_modified: current_date.to_string(),
-        pack_image: None,
-    });
-
-    let updated_content = match serde_yaml::to_string(&projects) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Failed to serialize project file: {}", e);
-            simple_toast(format!("Error creating project: {}", e).to_string(), app);
-            return serde_json::to_string(&CreateProject {
-                success: false,
-                id: None,
-                message: Some(e.to_string()),
-            })
-            .unwrap_or_else(|_| "Error serializing response".to_string());
+    // Registered before the import starts so it can update the entry
+    modify_projects(&app, |projects| {
+        if projects.iter().any(|p| p.path == dir_path) {
+            return Err(Error::conflict(
+                path,
+                "Selected path already exists in your projects",
+            ));
         }
-    };
 
-    let _ = fs::write(
-        path.join("project.yml"),
-        format!(
-            "name: {}\ndesription: {}\ninput:\n\tformat: raw",
-            name, project_description
-        ),
+        projects.push(Project {
+            id: project_id.clone(),
+            path: dir_path.clone(),
+            name: name.clone(),
+            description: Some(project_description.clone()),
+            date_modified: current_date.to_string(),
+            pack_image: None,
+        });
+        Ok(())
+    })
+    .map_err(fail)?;
+
+    let project_yml = ProjectYml::new(
+        name.clone(),
+        Some(project_description.clone()).filter(|description| !description.is_empty()),
+        InputFormat::Raw,
     );
+    if let Err(e) = store::write_yaml(&path.join("project.yml"), &project_yml) {
+        eprintln!("Failed to write project.yml: {}", e);
+    }
 
     if create_mc_dirs {
         try_create_directory(&path, &["assets", "minecraft"]);
@@ -232,308 +230,125 @@ pub fn create_project(
         }
     }
 
-    let path_clone = path.to_path_buf();
-    let import_zip_path_clone = import_zip_path.clone();
-    let app_cloned = app.clone();
+    let project_dir = path.to_path_buf();
+    let sink: Arc<dyn EventSink> = Arc::new(app.clone());
     let project_id_clone = project_id.clone();
-    let project_name_clone = name.clone();
-    tauri::async_runtime::spawn(async move {
-        let zip_path_str = import_zip_path_clone.unwrap_or("".to_string());
-        let zip_path = Path::new(&zip_path_str);
-
-        if zip_path.exists() {
-            #[allow(unused_must_use)]
-            {
-                app_cloned.emit("unzip-started", true);
-                let _ = unzip_and_process(
-                    zip_path,
-                    &path_clone,
-                    project_id_clone,
-                    project_name_clone,
-                    app_cloned.clone(),
-                )
-                .await;
-                app_cloned.emit("unzip-started", false);
-                app_cloned.emit("resync_dir_fe", false);
-            }
-        }
+    // Imports block on disk and image work, so they run on the blocking pool
+    tauri::async_runtime::spawn_blocking(move || {
+        import_pack(
+            import_zip_path,
+            import_dir_path,
+            &project_dir,
+            project_id_clone,
+            name,
+            import_options.unwrap_or_default(),
+            sink,
+        )
     });
 
-    if let Err(e) = fs::write(&projects_yml_path, updated_content) {
-        eprintln!("Failed to write project file: {}", e);
-        simple_toast(format!("Error creating project: {}", e).to_string(), app);
-        return serde_json::to_string(&CreateProject {
-            success: false,
-            id: None,
-            message: Some(e.to_string()),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string());
-    }
-
-    serde_json::to_string(&CreateProject {
-        success: true,
-        id: Some(project_id),
-        message: Some(format!("Created project: {}", name).to_string()),
-    })
-    .unwrap_or_else(|_| "Error serializing response".to_string())
+    Ok(project_id)
 }
 
-async fn unzip_and_process(
-    zip_path: &Path,
-    dest_dir: &Path,
+// Imports the chosen pack folder or zip, if any, into a freshly created
+// project and tells the user how it went
+fn import_pack(
+    import_zip_path: Option<String>,
+    import_dir_path: Option<String>,
+    project_dir: &Path,
     project_id: String,
     project_name: String,
-    app: tauri::AppHandle,
-) -> Result<()> {
-    let file = File::open(zip_path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
-    let mut tasks = Vec::new();
-
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let file_name = file.mangled_name();
-        let out_path = dest_dir.join(&file_name);
-
-        if file_name.to_string_lossy().ends_with('/') {
-            fs::create_dir_all(&out_path)?;
-        } else {
-            if let Some(parent) = out_path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)?;
-
-            let out_path_clone = out_path.clone();
-            let dest_dir_clone = dest_dir.to_path_buf();
-            let app_clone = app.clone();
-            let project_id_clone = project_id.clone();
-            let project_name_clone = project_name.clone();
-
-            let task = tokio::spawn(async move {
-                let mut out_file = File::create(&out_path_clone)?;
-                out_file.write_all(&buffer)?;
-
-                if out_path_clone.to_string_lossy().contains("pack.mcmeta") {
-                    let mc_meta_str = fs::read_to_string(&out_path_clone).unwrap();
-
-                    let mc_meta: McMeta = serde_json::from_str(&mc_meta_str)
-                        .expect("Failed to deserialize project file");
-
-                    let mut name = mc_meta.pack.name.unwrap_or(project_name_clone.clone());
-                    if name != "My New Project" {
-                        name = project_name_clone;
-                    }
-
-                    let new_project_yml = ProjectYml {
-                        name,
-                        description: Some(mc_meta.pack.description.unwrap_or("".to_string())),
-                        input: Input {
-                            format: "labpbr-1.3".to_string(),
-                        },
-                        profiles: None,
-                        tags: None,
-                    };
-                    update_project(project_id_clone, new_project_yml, app_clone.clone());
-                }
-
-                if let Some(ext) = out_path_clone.extension() {
-                    if out_path_clone.file_name() != Some(std::ffi::OsStr::new("pack.png"))
-                        && ext == "png"
-                    {
-                        let _ = process_image(&out_path_clone, &dest_dir_clone, app_clone);
-                    }
-                }
-                Ok::<_, anyhow::Error>(())
-            });
-
-            tasks.push(task);
-        }
-    }
-
-    for task in tasks {
-        task.await??;
+    options: ImportOptions,
+    sink: Arc<dyn EventSink>,
+) {
+    let zip_path_str = import_zip_path.unwrap_or("".to_string());
+    let zip_path = Path::new(&zip_path_str);
+    let dir_path_str = import_dir_path.unwrap_or("".to_string());
+    let import_dir = Path::new(&dir_path_str);
+
+    if !import_dir.is_dir() && !zip_path.exists() {
+        return;
     }
 
-    Ok(())
-}
-
-fn process_image(image_path: &Path, dest_dir: &Path, app: tauri::AppHandle) -> Result<()> {
-    let ignored_dirs = [
-        "colormap",
-        "effect",
-        "environment",
-        "font",
-        "gui",
-        "map",
-        "misc",
-        "mob_effect",
-        "models",
-    ];
-
-    let file_name = image_path
-        .file_name()
-        .unwrap()
-        .to_string_lossy()
-        .to_string();
-    let image_path_str = image_path.to_string_lossy().to_string();
-
-    if ignored_dirs.iter().any(|dir| image_path_str.contains(dir)) {
-        return Ok(());
-    }
-
-    let remove_suffix = |s: &str| s.replace("_n.png", ".png").replace("_s.png", ".png");
-    let material_dir = dest_dir.join(remove_suffix(&image_path_str).replace(".png", ""));
-
-    fs::create_dir_all(&material_dir)?;
-    fs::write(material_dir.join("mat.yml"), "")?;
-
-    let image_path_combined = dest_dir.join(image_path);
-
-    app.emit("unzip-progress", material_dir.to_string_lossy().to_string())?;
-
-    match file_name.as_str() {
-        name if name.ends_with("_n.png") => {
-            let _ = save_channel_map(
-                &material_dir,
-                2,
-                &image_path_combined,
-                String::from("ao.png"),
-                false,
-            );
-            let _ = save_channel_map(
-                &material_dir,
-                3,
-                &image_path_combined,
-                String::from("height.png"),
-                false,
-            );
-            let _ = save_normal(
-                &material_dir,
-                &image_path_combined,
-                String::from("normal.png"),
-            );
-
-            fs::remove_file(&image_path_combined)?;
-        }
-        name if name.ends_with("_s.png") => {
-            let _ = save_channel_map(
-                &material_dir,
-                0,
-                &image_path_combined,
-                String::from("smooth.png"),
-                false,
-            );
-            let _ = save_channel_map(
-                &material_dir,
-                3,
-                &image_path_combined,
-                String::from("emissive.png"),
-                true,
+    let result = if import_dir.is_dir() {
+        copy_and_process(
+            import_dir,
+            project_dir,
+            project_id,
+            project_name,
+            options,
+            sink.clone(),
+        )
+    } else {
+        unzip_and_process(
+            zip_path,
+            project_dir,
+            project_id,
+            project_name,
+            options,
+            sink.clone(),
+        )
+    };
+    match result {
+        Ok(report) => {
+            simple_toast(
+                format!(
+                    "Imported pack: {} converted, {} skipped, {} failed",
+                    report.converted.len(),
+                    report.skipped.len(),
+                    report.failed.len()
+                ),
+                sink.as_ref(),
             );
-            let _ = save_f0_hcm(&material_dir, &image_path_combined);
-            let _ = save_porosity_sss(&material_dir, &image_path_combined);
-
-            fs::remove_file(&image_path_combined)?;
         }
-        _ => {
-            fs::rename(&image_path_combined, material_dir.join("color.png"))?;
-            let _ = save_channel_map(
-                &material_dir,
-                3,
-                &material_dir.join("color.png"),
-                String::from("opacity.png"),
-                false,
-            );
+        Err(e) => {
+            eprintln!("Failed to import pack: {}", e);
+            simple_toast(format!("Error importing pack: {}", e), sink.as_ref());
         }
     }
-
-    Ok(())
+    sink.emit("resync_dir_fe", json!(false));
 }
 
 #[tauri::command]
 pub fn create_project_existing(
     project_yml_path: String,
     app: tauri::AppHandle,
-) -> Result<String, String> {
+) -> CommandResult<String> {
     let path = Path::new(&project_yml_path);
     if !path.exists() {
-        return Err(serde_json::to_string(&CreateExistingRes {
-            success: false,
-            id: None,
-            message: String::from("Failed to create project. Selected project.yml does not exist."),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string()));
+        return Err(Error::not_found(
+            path,
+            "Selected project.yml does not exist",
+        ));
     }
 
-    let project_yml_str = fs::read_to_string(path).map_err(|e| {
-        eprintln!("Failed to read project.yml: {}", e);
-        return serde_json::to_string(&CreateExistingRes {
-            success: false,
-            id: None,
-            message: String::from(format!("Failed to read project.yml: {}", e)),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string());
-    })?;
-
-    let project_yml: ProjectYml = serde_yaml::from_str(&project_yml_str)
-        .map_err(|e| format!("Failed to deserialize project file: {}", e))?;
+    let project_yml_str = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+    let project_yml = parse_project_yml(path, &project_yml_str)?;
     let project_path = project_yml_path.replace("/project.yml", "");
     let project_id = String::from(uuid::Uuid::new_v4());
     let project_name = project_yml.name;
     let project_desc = project_yml.description.unwrap_or("".to_string());
     let date_modified = chrono::prelude::Utc::now();
 
-    let config_dir = get_config_dir(&app);
-    let projects_yml_path = Path::new(&config_dir).join("projects.yml");
-
-    let mut projects: Vec<Project> = get_projects_vec(&app);
-    if projects.iter().any(|p| p.path == project_path) {
-        return Err(serde_json::to_string(&CreateProject {
-            success: false,
-            id: None,
-            message: Some("Selected path already exists in your projects".to_string()),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string()));
-    }
-
-    projects.push(Project {
-        id: project_id.clone(),
-        path: project_path,
-        name: project_name,
-        description: Some(project_desc),
-        date_modified: date_modified.to_string(),
-        pack_image: None,
-    });
-
-    let updated_content = match serde_yaml::to_string(&projects) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Failed to serialize project file: {}", e);
-            return Err(serde_json::to_string(&CreateExistingRes {
-                success: false,
-                id: None,
-                message: e.to_string(),
-            })
-            .unwrap_or_else(|_| "Error serializing response".to_string()));
+    modify_projects(&app, |projects| {
+        if projects.iter().any(|p| p.path == project_path) {
+            return Err(Error::conflict(
+                &project_path,
+                "Selected path already exists in your projects",
+            ));
         }
-    };
 
-    if let Err(e) = fs::write(&projects_yml_path, updated_content) {
-        eprintln!("Failed to write project file: {}", e);
-        return Err(serde_json::to_string(&CreateExistingRes {
-            success: false,
-            id: None,
-            message: format!("Failed to write to your projects: {}", e.to_string()).to_string(),
-        })
-        .unwrap_or_else(|_| "Error serializing response".to_string()));
-    }
+        projects.push(Project {
+            id: project_id.clone(),
+            path: project_path,
+            name: project_name,
+            description: Some(project_desc),
+            date_modified: date_modified.to_string(),
+            pack_image: None,
+        });
+        Ok(())
+    })?;
 
-    Ok(serde_json::to_string(&CreateExistingRes {
-        success: true,
-        id: Some(project_id),
-        message: String::from("Successfully added project"),
-    })
-    .unwrap_or_else(|_| "Error serializing response".to_string()))
+    Ok(project_id)
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
@@ -546,28 +361,73 @@ pub struct Project {
     pub date_modified: String,
 }
 
-#[derive(serde::Serialize)]
-pub struct CreateProject {
-    pub success: bool,
-    pub id: Option<String>,
-    pub message: Option<String>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        events::RecordingSink,
+        import::progress::{FINISHED_EVENT, PROGRESS_EVENT},
+    };
+    use image::{Rgba, RgbaImage};
 
-#[derive(serde::Deserialize, serde::Serialize)]
-pub struct McMeta {
-    pack: Pack,
-}
+    fn png(path: &Path, pixel: [u8; 4]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        RgbaImage::from_pixel(2, 2, Rgba(pixel)).save(path).unwrap();
+    }
 
-#[derive(serde::Deserialize, serde::Serialize)]
-pub struct Pack {
-    name: Option<String>,
-    description: Option<String>,
-    pack_format: Option<u8>,
-}
+    #[test]
+    fn import_reports_progress() {
+        let (pack, project) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let textures = pack.path().join("assets/minecraft/textures");
+        fs::write(
+            pack.path().join("pack.mcmeta"),
+            r#"{"pack": {"pack_format": 34, "description": "Test"}}"#,
+        )
+        .unwrap();
+        png(&textures.join("block/stone.png"), [100, 100, 100, 255]);
+        png(&textures.join("block/stone_n.png"), [128, 128, 255, 255]);
+        png(&textures.join("gui/widgets.png"), [0, 0, 0, 255]);
+
+        let sink = Arc::new(RecordingSink::default());
+        import_pack(
+            None,
+            Some(pack.path().to_string_lossy().to_string()),
+            project.path(),
+            "progress-test".to_string(),
+            "Test".to_string(),
+            ImportOptions::default(),
+            sink.clone(),
+        );
+
+        let progress = sink.events(PROGRESS_EVENT);
+        assert!(progress.iter().all(|p| p["project_id"] == "progress-test"));
+        assert_eq!(progress[0]["stage"], "extracting");
+        assert_eq!(progress[0]["total_entries"], 4);
+        let mut stages: Vec<_> = progress.iter().map(|p| p["stage"].clone()).collect();
+        stages.dedup();
+        assert_eq!(stages, vec!["extracting", "converting", "finishing"]);
+        assert_eq!(progress.last().unwrap()["extracted"], 4);
+
+        let finished = sink.events(FINISHED_EVENT);
+        assert_eq!(finished.len(), 1);
+        let summary = &finished[0];
+        assert_eq!(summary["success"], true);
+        assert_eq!(summary["cancelled"], false);
+        assert!(summary["error"].is_null());
+        assert_eq!(
+            (&summary["decoded"], &summary["skipped"], &summary["failed"]),
+            (&json!(2), &json!(1), &json!(0))
+        );
+        assert_eq!(
+            summary["report"]["skipped"][0]["path"],
+            "assets/minecraft/textures/gui/widgets.png"
+        );
 
-#[derive(serde::Serialize)]
-struct CreateExistingRes {
-    success: bool,
-    id: Option<String>,
-    message: String,
+        assert_eq!(
+            *sink.toasts.lock().unwrap(),
+            vec!["Imported pack: 2 converted, 1 skipped, 0 failed"]
+        );
+        let events = sink.events.lock().unwrap();
+        assert_eq!(events.last().unwrap().0, "resync_dir_fe");
+    }
 }
@@ -1,8 +1,11 @@
-use image::{GenericImageView, ImageBuffer, Luma, Rgba};
+use image::{GenericImageView, ImageBuffer, Luma, RgbaImage};
 use rayon::prelude::*;
 use std::error::Error;
 use std::path::Path;
 
+use super::codec::{labpbr, oldpbr};
+use super::project::structs::InputFormat;
+
 pub fn save_channel_map(
     material_dir: &Path,
     channel: usize,
@@ -60,161 +63,110 @@ pub fn save_channel_map(
     Ok(())
 }
 
-pub fn save_f0_hcm(material_dir: &Path, image_path: &Path) -> Result<(), Box<dyn Error>> {
-    let img = image::open(image_path).map_err(|e| {
-        eprintln!("Error opening image: {}", e);
-        eprintln!("Error opening image path: {}", image_path.to_string_lossy(),);
-        e
-    })?;
-
-    let (width, height) = img.dimensions();
-    let mut f0_map: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
-    let mut hcm_map: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
-
-    let mut process_images = || {
-        f0_map
-            .par_chunks_exact_mut(width as usize * 4)
-            .zip(hcm_map.par_chunks_exact_mut(width as usize * 4))
-            .enumerate()
-            .for_each(|(y, (f0_row, hcm_row))| {
-                for (x, (f0_chunk, hcm_chunk)) in f0_row
-                    .chunks_exact_mut(4)
-                    .zip(hcm_row.chunks_exact_mut(4))
-                    .enumerate()
-                {
-                    let pixel = img.get_pixel(x as u32, y as u32);
-                    let value = pixel[1];
-
-                    if value < 230 {
-                        f0_chunk.copy_from_slice(&[value, value, value, 255]);
-                        hcm_chunk.copy_from_slice(&[0, 0, 0, 255]);
-                    } else {
-                        f0_chunk.copy_from_slice(&[0, 0, 0, 255]);
-                        hcm_chunk.copy_from_slice(&[value, value, value, 255]);
-                    }
-                }
-            });
-    };
-
-    process_images();
-
-    f0_map.save(material_dir.join("f0.png")).map_err(|e| {
-        eprintln!("Error saving f0 map: {}", e);
-        e
-    })?;
-
-    hcm_map.save(material_dir.join("hcm.png")).map_err(|e| {
-        eprintln!("Error saving hcm map: {}", e);
+fn save_map(map: &ImageBuffer<Luma<u8>, Vec<u8>>, path: &Path) -> Result<(), Box<dyn Error>> {
+    map.save(path).map_err(|e| {
+        eprintln!("Error saving {}: {}", path.to_string_lossy(), e);
         e
     })?;
 
     Ok(())
 }
 
-pub fn save_porosity_sss(material_dir: &Path, image_path: &Path) -> Result<(), Box<dyn Error>> {
+// Splits a packed `_n.png` into normal.png, ao.png and height.png. LabPBR 1.3
+// gets its Z rebuilt from X/Y, older layouts already carry it in blue. AO and
+// height are only written when the texture uses them.
+pub fn save_packed_normal(
+    material_dir: &Path,
+    image_path: &Path,
+    format: InputFormat,
+) -> Result<(), Box<dyn Error>> {
     let img = image::open(image_path).map_err(|e| {
         eprintln!("Error opening image: {}", e);
         eprintln!("Error opening image path: {}", image_path.to_string_lossy(),);
         e
     })?;
 
-    let (width, height) = img.dimensions();
-    let mut porosity_map: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
-    let mut sss_map: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
-
-    let mut process_images = || {
-        porosity_map
-            .par_chunks_exact_mut(width as usize * 4)
-            .zip(sss_map.par_chunks_exact_mut(width as usize * 4))
-            .enumerate()
-            .for_each(|(y, (porosity_row, sss_row))| {
-                for (x, (porosity_chunk, sss_chunk)) in porosity_row
-                    .chunks_exact_mut(4)
-                    .zip(sss_row.chunks_exact_mut(4))
-                    .enumerate()
-                {
-                    let pixel = img.get_pixel(x as u32, y as u32);
-                    let value = pixel[2];
-
-                    if value <= 127 {
-                        let transformed_value = value.saturating_mul(2);
-                        porosity_chunk.copy_from_slice(&[
-                            transformed_value,
-                            transformed_value,
-                            transformed_value,
-                            255,
-                        ]);
-                        sss_chunk.copy_from_slice(&[0, 0, 0, 255]);
-                    } else {
-                        let transformed_value = value.saturating_sub(128).saturating_mul(2);
-                        porosity_chunk.copy_from_slice(&[0, 0, 0, 255]);
-                        sss_chunk.copy_from_slice(&[
-                            transformed_value,
-                            transformed_value,
-                            transformed_value,
-                            255,
-                        ]);
-                    }
-                }
-            });
+    let channels = match format {
+        InputFormat::LabPbr13 => labpbr::decode_normal(&img.to_rgba8()),
+        _ => labpbr::decode_normal_v1_2(&img.to_rgba8()),
     };
 
-    process_images();
-
-    porosity_map
-        .save(material_dir.join("porosity.png"))
+    channels
+        .normal
+        .save(material_dir.join("normal.png"))
         .map_err(|e| {
-            eprintln!("Error saving porosity map: {}", e);
+            eprintln!("Error saving normal map: {}", e);
             e
         })?;
 
-    sss_map.save(material_dir.join("sss.png")).map_err(|e| {
-        eprintln!("Error saving sss map: {}", e);
-        e
-    })?;
+    if let Some(occlusion) = &channels.occlusion {
+        save_map(occlusion, &material_dir.join("ao.png"))?;
+    }
+    if let Some(height) = &channels.height {
+        save_map(height, &material_dir.join("height.png"))?;
+    }
 
     Ok(())
 }
 
-pub fn save_normal(
+// Splits a packed `_s.png` into separate channel files
+pub fn save_packed_specular(
     material_dir: &Path,
     image_path: &Path,
-    save_name: String,
+    format: InputFormat,
 ) -> Result<(), Box<dyn Error>> {
-    let save_file_name = save_name;
-    let img = image::open(&image_path).map_err(|e| {
+    let img = image::open(image_path).map_err(|e| {
         eprintln!("Error opening image: {}", e);
-        eprintln!(
-            "Error opening image path and file name: {}\n{}",
-            image_path.to_string_lossy(),
-            save_file_name
-        );
+        eprintln!("Error opening image path: {}", image_path.to_string_lossy(),);
         e
     })?;
 
-    let (width, height) = img.dimensions();
-    let mut normal_map: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
+    match format {
+        InputFormat::OldPbr => save_oldpbr_specular(material_dir, &img.to_rgba8()),
+        _ => save_labpbr_specular(material_dir, &img.to_rgba8()),
+    }
+}
 
-    normal_map
-        .par_chunks_mut(width as usize * 4)
-        .enumerate()
-        .for_each(|(y, row)| {
-            for x in 0..width {
-                let pixel = img.get_pixel(x, y as u32);
-                let idx = (x as usize) * 4;
-                row[idx] = pixel[0];
-                row[idx + 1] = pixel[1];
-                row[idx + 2] = 255;
-                row[idx + 3] = 255;
-            }
-        });
+// smooth.png, f0.png, hcm.png, porosity.png, sss.png and emissive.png
+fn save_labpbr_specular(material_dir: &Path, img: &RgbaImage) -> Result<(), Box<dyn Error>> {
+    let channels = labpbr::decode_specular(img);
+
+    save_map(&channels.smooth, &material_dir.join("smooth.png"))?;
+    save_map(&channels.f0, &material_dir.join("f0.png"))?;
+
+    let optional = [
+        (&channels.hcm, "hcm.png"),
+        (&channels.porosity, "porosity.png"),
+        (&channels.sss, "sss.png"),
+        (&channels.emissive, "emissive.png"),
+    ];
+    for (map, file_name) in optional {
+        if let Some(map) = map {
+            save_map(map, &material_dir.join(file_name))?;
+        }
+    }
 
-    normal_map
-        .save(material_dir.join(save_file_name))
-        .map_err(|e| {
-            eprintln!("Error saving normal map: {}", e);
-            e
-        })?;
+    Ok(())
+}
+
+// smooth.png, metal.png and emissive.png, plus the metal mask converted to
+// f0.png and hcm.png so LabPBR exports keep the metals
+fn save_oldpbr_specular(material_dir: &Path, img: &RgbaImage) -> Result<(), Box<dyn Error>> {
+    let channels = oldpbr::decode_specular(img);
+
+    save_map(&channels.smooth, &material_dir.join("smooth.png"))?;
+
+    if let Some(metal) = &channels.metal {
+        let converted = oldpbr::metal_mask_to_f0(metal);
+        save_map(metal, &material_dir.join("metal.png"))?;
+        save_map(&converted.f0, &material_dir.join("f0.png"))?;
+        if let Some(hcm) = &converted.hcm {
+            save_map(hcm, &material_dir.join("hcm.png"))?;
+        }
+    }
+    if let Some(emissive) = &channels.emissive {
+        save_map(emissive, &material_dir.join("emissive.png"))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,521 @@
+// Bedrock Edition RTX importer. Every `<name>.texture_set.json` becomes a
+// `<name>/` material folder with separate channel files and a mat.yml.
+
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage};
+use rayon::prelude::*;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    progress::ProgressTracker, retire_original, rules::CompiledRules, ImportFailure, ImportReport,
+    ImportSkipped,
+};
+use crate::core::{
+    export::zip_path,
+    interface::structs::{DefaultsGrayscale, MatYml},
+    project::structs::DirectoryBehavior,
+};
+
+const TEXTURE_SET_SUFFIX: &str = ".texture_set.json";
+const IMAGE_EXTENSIONS: [&str; 4] = ["png", "tga", "jpg", "jpeg"];
+
+#[derive(serde::Deserialize)]
+pub struct BedrockManifest {
+    pub header: ManifestHeader,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ManifestHeader {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct TextureSetFile {
+    #[serde(rename = "minecraft:texture_set")]
+    pub texture_set: TextureSet,
+}
+
+#[derive(serde::Deserialize)]
+pub struct TextureSet {
+    pub color: Option<TextureSetLayer>,
+    pub metalness_emissive_roughness: Option<TextureSetLayer>,
+    pub metalness_emissive_roughness_subsurface: Option<TextureSetLayer>,
+    pub normal: Option<String>,
+    pub heightmap: Option<String>,
+}
+
+// A layer is either the name of a texture next to the texture set, or a
+// uniform value given as an array or a `#RRGGBB(AA)` hex string
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+pub enum TextureSetLayer {
+    Texture(String),
+    Uniform(Vec<f32>),
+}
+
+enum Layer {
+    Texture(RgbaImage),
+    Uniform(Vec<u8>),
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 && hex.len() != 8 {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn find_layer_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.exists())
+}
+
+fn load_layer(dir: &Path, layer: &TextureSetLayer, consumed: &mut Vec<PathBuf>) -> Result<Layer> {
+    match layer {
+        TextureSetLayer::Uniform(values) => Ok(Layer::Uniform(
+            values.iter().map(|v| v.clamp(0.0, 255.0) as u8).collect(),
+        )),
+        TextureSetLayer::Texture(name) => {
+            if let Some(values) = parse_hex(name) {
+                return Ok(Layer::Uniform(values));
+            }
+
+            let file = find_layer_file(dir, name)
+                .ok_or_else(|| anyhow!("Texture set layer not found: {}", name))?;
+            let img = image::open(&file)?.to_rgba8();
+            consumed.push(file);
+
+            Ok(Layer::Texture(img))
+        }
+    }
+}
+
+// Names of the layers in a texture set that point at actual textures
+fn texture_names(texture_set: &TextureSet) -> impl Iterator<Item = &str> {
+    [
+        &texture_set.metalness_emissive_roughness_subsurface,
+        &texture_set.metalness_emissive_roughness,
+    ]
+    .into_iter()
+    .filter_map(|layer| match layer {
+        Some(TextureSetLayer::Texture(name)) if parse_hex(name).is_none() => Some(name.as_str()),
+        _ => None,
+    })
+    .chain(
+        [&texture_set.normal, &texture_set.heightmap]
+            .into_iter()
+            .filter_map(|name| name.as_deref()),
+    )
+}
+
+fn load_texture(dir: &Path, name: &str, consumed: &mut Vec<PathBuf>) -> Result<DynamicImage> {
+    let file = find_layer_file(dir, name).ok_or_else(|| anyhow!("Texture not found: {}", name))?;
+    let img = image::open(&file)?;
+    consumed.push(file);
+
+    Ok(img)
+}
+
+fn save_channel(img: &RgbaImage, channel: usize, path: &Path) -> Result<()> {
+    GrayImage::from_fn(img.width(), img.height(), |x, y| {
+        image::Luma([img.get_pixel(x, y)[channel]])
+    })
+    .save(path)?;
+
+    Ok(())
+}
+
+fn uniform(value: u8) -> Option<DefaultsGrayscale> {
+    Some(DefaultsGrayscale {
+        value: Some(value as f32),
+        ..Default::default()
+    })
+}
+
+// Imports one texture set and returns the source files it was built from
+pub fn import_texture_set(texture_set_path: &Path) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let dir = texture_set_path
+        .parent()
+        .ok_or_else(|| anyhow!("Texture set has no parent folder"))?;
+    let file_name = texture_set_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let name = file_name.trim_end_matches(TEXTURE_SET_SUFFIX);
+
+    let texture_set_str = fs::read_to_string(texture_set_path)?;
+    let texture_set = serde_json::from_str::<TextureSetFile>(&texture_set_str)?.texture_set;
+
+    let material_dir = dir.join(name);
+    fs::create_dir_all(&material_dir)?;
+
+    let mut consumed = vec![texture_set_path.to_path_buf()];
+    let mut mat_yml = MatYml::new();
+
+    match texture_set
+        .color
+        .as_ref()
+        .map(|layer| load_layer(dir, layer, &mut consumed))
+        .transpose()?
+    {
+        Some(Layer::Texture(color)) => {
+            if color.pixels().any(|p| p[3] < 255) {
+                save_channel(&color, 3, &material_dir.join("opacity.png"))?;
+            }
+            color.save(material_dir.join("color.png"))?;
+        }
+        Some(Layer::Uniform(values)) => {
+            // Uniform colours have no size of their own, so borrow one from
+            // another layer or fall back to the vanilla 16x16
+            let (width, height) = texture_names(&texture_set)
+                .filter_map(|name| find_layer_file(dir, name))
+                .find_map(|file| image::image_dimensions(file).ok())
+                .unwrap_or((16, 16));
+            let alpha = values.get(3).copied().unwrap_or(255);
+            let pixel = Rgba([
+                values.first().copied().unwrap_or(0),
+                values.get(1).copied().unwrap_or(0),
+                values.get(2).copied().unwrap_or(0),
+                alpha,
+            ]);
+            RgbaImage::from_pixel(width, height, pixel).save(material_dir.join("color.png"))?;
+            if alpha < 255 {
+                mat_yml.opacity = uniform(alpha);
+            }
+        }
+        None => (),
+    }
+
+    let (mer, subsurface) = match (
+        &texture_set.metalness_emissive_roughness_subsurface,
+        &texture_set.metalness_emissive_roughness,
+    ) {
+        (Some(layer), _) => (Some(load_layer(dir, layer, &mut consumed)?), true),
+        (None, Some(layer)) => (Some(load_layer(dir, layer, &mut consumed)?), false),
+        (None, None) => (None, false),
+    };
+
+    match mer {
+        Some(Layer::Texture(mer)) => {
+            save_channel(&mer, 0, &material_dir.join("metal.png"))?;
+            save_channel(&mer, 1, &material_dir.join("emissive.png"))?;
+            save_channel(&mer, 2, &material_dir.join("rough.png"))?;
+            if subsurface {
+                save_channel(&mer, 3, &material_dir.join("sss.png"))?;
+            }
+        }
+        Some(Layer::Uniform(values)) => {
+            let channel = |i: usize| values.get(i).copied().unwrap_or(0);
+
+            // A value of 0 means "no override" in mat.yml, which already
+            // matches the default for every channel except roughness
+            if channel(0) > 0 {
+                mat_yml.metal = uniform(channel(0));
+            }
+            if channel(1) > 0 {
+                mat_yml.emissive = uniform(channel(1));
+            }
+            if channel(2) > 0 {
+                mat_yml.rough = uniform(channel(2));
+            } else {
+                mat_yml.smooth = uniform(255);
+            }
+            if subsurface && channel(3) > 0 {
+                mat_yml.sss = uniform(channel(3));
+            }
+        }
+        None => (),
+    }
+
+    if let Some(normal) = &texture_set.normal {
+        let normal = load_texture(dir, normal, &mut consumed)?.to_rgb8();
+        normal.save(material_dir.join("normal.png"))?;
+    } else if let Some(heightmap) = &texture_set.heightmap {
+        let height = load_texture(dir, heightmap, &mut consumed)?.to_luma8();
+        height.save(material_dir.join("height.png"))?;
+    }
+
+    fs::write(
+        material_dir.join("mat.yml"),
+        serde_yaml::to_string(&mat_yml)?,
+    )?;
+
+    Ok((material_dir, consumed))
+}
+
+pub fn find_texture_sets(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(TEXTURE_SET_SUFFIX)
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files
+}
+
+// The texture a texture set is named after, relative to `root`. Import rules
+// are matched against it, as they are against Java textures.
+fn rules_path(root: &Path, texture_set: &Path) -> String {
+    let file_name = texture_set
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let name = file_name
+        .strip_suffix(TEXTURE_SET_SUFFIX)
+        .unwrap_or(&file_name);
+    let dir = texture_set.parent().unwrap_or(root);
+    let texture = find_layer_file(dir, name).unwrap_or(dir.join(format!("{}.png", name)));
+
+    zip_path(texture.strip_prefix(root).unwrap_or(&texture))
+}
+
+// Converts every texture set under `root` into material folders, leaving out
+// the ones the import rules keep as they are. The packed source files are
+// only retired once all sets have been read, and only when every set using
+// them was converted.
+pub fn import_texture_sets(
+    root: &Path,
+    rules: &CompiledRules,
+    keep_originals: bool,
+    progress: &ProgressTracker,
+) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut texture_sets = Vec::new();
+
+    for texture_set in find_texture_sets(root) {
+        let path = rules_path(root, &texture_set);
+        if rules.directory_behavior(&path) != DirectoryBehavior::Convert {
+            continue;
+        }
+        match rules.skip_reason(&path) {
+            Some(reason) => {
+                let path = zip_path(texture_set.strip_prefix(root).unwrap_or(&texture_set));
+                progress.skipped(&path);
+                report.skipped.push(ImportSkipped { path, reason });
+            }
+            None => texture_sets.push(texture_set),
+        }
+    }
+
+    let results: Vec<_> = texture_sets
+        .par_iter()
+        .map(|texture_set| {
+            let result = import_texture_set(texture_set);
+            let path = texture_set.strip_prefix(root).unwrap_or(texture_set);
+            match &result {
+                Ok(_) => progress.decoded(&path.to_string_lossy()),
+                Err(e) => {
+                    eprintln!(
+                        "Failed to import texture set {}: {}",
+                        texture_set.display(),
+                        e
+                    );
+                    progress.failed(&path.to_string_lossy());
+                }
+            }
+            (texture_set.clone(), result)
+        })
+        .collect();
+
+    let mut consumed_files = HashSet::new();
+    let mut failed_files = HashSet::new();
+
+    for (texture_set, result) in results {
+        let path = texture_set
+            .strip_prefix(root)
+            .unwrap_or(&texture_set)
+            .to_string_lossy()
+            .to_string();
+
+        match result {
+            Ok((_, consumed)) => {
+                report.converted.push(path);
+                consumed_files.extend(consumed);
+            }
+            Err(e) => {
+                report.failed.push(ImportFailure {
+                    path,
+                    error: e.to_string(),
+                });
+                // Keep whatever the failed set may have read, it's all the
+                // user has left of it
+                let dir = texture_set.parent().unwrap_or(root);
+                failed_files.extend(
+                    texture_names_in(&texture_set)
+                        .into_iter()
+                        .filter_map(|name| find_layer_file(dir, &name)),
+                );
+            }
+        }
+    }
+
+    // Several texture sets may share a texture, so only retire them at the end
+    for file in consumed_files.difference(&failed_files) {
+        if let Err(e) = retire_original(root, file, keep_originals) {
+            eprintln!("Failed to retire {}: {}", file.display(), e);
+        }
+    }
+
+    Ok(report)
+}
+
+// Every texture a texture set file points at, as far as it can be read
+fn texture_names_in(texture_set_path: &Path) -> Vec<String> {
+    let texture_set = fs::read_to_string(texture_set_path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<TextureSetFile>(&s).ok());
+
+    match texture_set {
+        Some(file) => {
+            let mut names: Vec<String> = texture_names(&file.texture_set)
+                .map(str::to_string)
+                .collect();
+            if let Some(TextureSetLayer::Texture(name)) = &file.texture_set.color {
+                names.push(name.clone());
+            }
+            names
+        }
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+    use serde_json::json;
+
+    fn texture_set(dir: &Path, json: serde_json::Value) -> PathBuf {
+        let path = dir.join("ore.texture_set.json");
+        fs::write(
+            &path,
+            json!({ "format_version": "1.16.100", "minecraft:texture_set": json }).to_string(),
+        )
+        .unwrap();
+        path
+    }
+
+    fn pixel(path: &Path) -> u8 {
+        image::open(path).unwrap().to_luma8().get_pixel(0, 0)[0]
+    }
+
+    fn mat_yml(material_dir: &Path) -> serde_yaml::Value {
+        serde_yaml::from_str(&fs::read_to_string(material_dir.join("mat.yml")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn hex_colours() {
+        assert_eq!(parse_hex("#102030"), Some(vec![16, 32, 48]));
+        assert_eq!(parse_hex("#102030ff"), Some(vec![16, 32, 48, 255]));
+        assert_eq!(parse_hex("102030"), None);
+        assert_eq!(parse_hex("#1020"), None);
+        assert_eq!(parse_hex("#10203g"), None);
+    }
+
+    #[test]
+    fn uniform_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = texture_set(
+            dir.path(),
+            json!({
+                "color": "#10203080",
+                "metalness_emissive_roughness": [0, 0, 255],
+            }),
+        );
+
+        let (material_dir, consumed) = import_texture_set(&path).unwrap();
+        assert_eq!(material_dir, dir.path().join("ore"));
+        assert_eq!(consumed, vec![path]);
+
+        let color = image::open(material_dir.join("color.png"))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(color.dimensions(), (16, 16));
+        assert_eq!(color.get_pixel(0, 0).0, [16, 32, 48, 128]);
+
+        let mat_yml = mat_yml(&material_dir);
+        assert_eq!(mat_yml["rough"]["value"].as_f64(), Some(255.0));
+        assert_eq!(mat_yml["opacity"]["value"].as_f64(), Some(128.0));
+        assert!(mat_yml.get("metal").is_none());
+        assert!(mat_yml.get("emissive").is_none());
+        assert!(mat_yml.get("smooth").is_none());
+    }
+
+    #[test]
+    fn mers_texture_is_split() {
+        let dir = tempfile::tempdir().unwrap();
+        RgbaImage::from_pixel(2, 2, Rgba([200, 150, 100, 255]))
+            .save(dir.path().join("ore.png"))
+            .unwrap();
+        RgbaImage::from_pixel(2, 2, Rgba([10, 20, 30, 40]))
+            .save(dir.path().join("ore_mers.png"))
+            .unwrap();
+        GrayImage::from_pixel(2, 2, Luma([90]))
+            .save(dir.path().join("ore_heightmap.png"))
+            .unwrap();
+        let path = texture_set(
+            dir.path(),
+            json!({
+                "color": "ore",
+                "metalness_emissive_roughness_subsurface": "ore_mers",
+                "heightmap": "ore_heightmap",
+            }),
+        );
+
+        let (material_dir, consumed) = import_texture_set(&path).unwrap();
+        assert_eq!(pixel(&material_dir.join("metal.png")), 10);
+        assert_eq!(pixel(&material_dir.join("emissive.png")), 20);
+        assert_eq!(pixel(&material_dir.join("rough.png")), 30);
+        assert_eq!(pixel(&material_dir.join("sss.png")), 40);
+        assert_eq!(pixel(&material_dir.join("height.png")), 90);
+        // An opaque colour needs no opacity file
+        assert!(!material_dir.join("opacity.png").exists());
+
+        let names: HashSet<_> = consumed
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            HashSet::from(
+                [
+                    "ore.texture_set.json",
+                    "ore.png",
+                    "ore_mers.png",
+                    "ore_heightmap.png"
+                ]
+                .map(String::from)
+            )
+        );
+    }
+}
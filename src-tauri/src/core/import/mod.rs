@@ -0,0 +1,122 @@
+pub mod bedrock;
+pub mod pack;
+pub mod progress;
+pub mod rules;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+// Where non-destructive imports keep the packed textures they decoded, with
+// the same layout as the pack itself
+pub const ORIGINALS_DIR: &str = ".original";
+
+// Cancel flags of the imports in progress, keyed by project id
+static RUNNING_IMPORTS: Mutex<Vec<(String, Arc<AtomicBool>)>> = Mutex::new(Vec::new());
+
+#[derive(serde::Serialize, Default)]
+pub struct ImportReport {
+    pub converted: Vec<String>,
+    pub skipped: Vec<ImportSkipped>,
+    pub failed: Vec<ImportFailure>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ImportSkipped {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct ImportFailure {
+    pub path: String,
+    pub error: String,
+}
+
+impl ImportReport {
+    pub fn merge(&mut self, other: ImportReport) {
+        self.converted.extend(other.converted);
+        self.skipped.extend(other.skipped);
+        self.failed.extend(other.failed);
+    }
+}
+
+pub fn originals_path(root: &Path, path: &Path) -> PathBuf {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    root.join(ORIGINALS_DIR).join(relative)
+}
+
+// Gets a decoded source file out of the way: moved into ORIGINALS_DIR when
+// originals are kept, deleted otherwise
+pub fn retire_original(root: &Path, path: &Path, keep_originals: bool) -> io::Result<()> {
+    if !keep_originals {
+        return fs::remove_file(path);
+    }
+
+    let backup = originals_path(root, path);
+    if let Some(parent) = backup.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(path, backup)
+}
+
+pub fn start_import(project_id: &str, cancelled: Arc<AtomicBool>) {
+    if let Ok(mut imports) = RUNNING_IMPORTS.lock() {
+        imports.push((project_id.to_string(), cancelled));
+    }
+}
+
+pub fn end_import(project_id: &str) {
+    if let Ok(mut imports) = RUNNING_IMPORTS.lock() {
+        imports.retain(|(id, _)| id != project_id);
+    }
+}
+
+// Asks running imports to stop, all of them when no project is given. The
+// import rolls the project back to how it was before once it notices.
+#[tauri::command]
+pub fn cancel_import(project_id: Option<String>) -> bool {
+    let Ok(imports) = RUNNING_IMPORTS.lock() else {
+        return false;
+    };
+
+    let mut found = false;
+    for (id, cancelled) in imports.iter() {
+        if project_id.is_none() || project_id.as_ref() == Some(id) {
+            cancelled.store(true, Ordering::Relaxed);
+            found = true;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retired_originals_keep_their_path() {
+        let root = tempfile::tempdir().unwrap();
+        let texture = root
+            .path()
+            .join("assets/minecraft/textures/block/stone_n.png");
+        fs::create_dir_all(texture.parent().unwrap()).unwrap();
+        fs::write(&texture, b"normal").unwrap();
+
+        retire_original(root.path(), &texture, true).unwrap();
+        assert!(!texture.exists());
+        let kept = root
+            .path()
+            .join(".original/assets/minecraft/textures/block/stone_n.png");
+        assert_eq!(fs::read(kept).unwrap(), b"normal");
+
+        fs::write(&texture, b"normal").unwrap();
+        retire_original(root.path(), &texture, false).unwrap();
+        assert!(!texture.exists());
+    }
+}
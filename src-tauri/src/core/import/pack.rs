@@ -0,0 +1,985 @@
+// Imports a Java or Bedrock resource pack into a project folder, either from
+// a zip archive or from an unzipped pack folder. Files are first put on disk
+// one by one, then run through `process_file` on the rayon pool. A cancelled
+// or failed import rolls the project back to how it was before.
+
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use super::{
+    bedrock::{import_texture_sets, BedrockManifest},
+    end_import, originals_path,
+    progress::{ImportStage, ProgressTracker},
+    retire_original,
+    rules::{load_import_rules, CompiledRules},
+    start_import, ImportFailure, ImportReport, ImportSkipped,
+};
+use crate::core::{
+    error::Error,
+    events::EventSink,
+    export::zip_path,
+    image_process::{save_channel_map, save_packed_normal, save_packed_specular},
+    interface::structs::{MatYml, TEXTURE_FILES},
+    project::{
+        structs::{DirectoryBehavior, ImportRules, InputFormat, ProjectYml},
+        update_project, write_project_yml,
+    },
+};
+
+// Archives past these limits are refused before anything is written
+pub const MAX_ENTRIES: usize = 200_000;
+pub const MAX_UNCOMPRESSED_SIZE: u64 = 8 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Copy)]
+struct ArchiveLimits {
+    entries: usize,
+    size: u64,
+}
+
+const ARCHIVE_LIMITS: ArchiveLimits = ArchiveLimits {
+    entries: MAX_ENTRIES,
+    size: MAX_UNCOMPRESSED_SIZE,
+};
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct McMeta {
+    pack: Pack,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Pack {
+    name: Option<String>,
+    // Either a plain string or a JSON text component
+    description: Option<serde_json::Value>,
+    pack_format: Option<u32>,
+}
+
+impl Pack {
+    fn description_text(&self) -> String {
+        match &self.description {
+            Some(serde_json::Value::String(text)) => text.clone(),
+            Some(serde_json::Value::Object(component)) => component
+                .get("text")
+                .and_then(|text| text.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            Some(serde_json::Value::Array(components)) => components
+                .iter()
+                .filter_map(|c| c.as_str().or_else(|| c.get("text")?.as_str()))
+                .collect(),
+            _ => String::new(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ImportOptions {
+    // How the pack's packed textures are laid out
+    pub format: InputFormat,
+    // Move decoded textures into ORIGINALS_DIR instead of deleting them
+    pub keep_originals: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            format: InputFormat::LabPbr13,
+            keep_originals: false,
+        }
+    }
+}
+
+// Where the report of the last import is kept inside the project
+pub const IMPORT_REPORT_PATH: &str = ".pixeltoolkit/import-report.json";
+
+// Everything a single imported file needs to know about the import
+struct ImportContext {
+    dest_dir: PathBuf,
+    project_id: String,
+    project_name: String,
+    options: ImportOptions,
+    is_bedrock: bool,
+    import_rules: ImportRules,
+    rules: CompiledRules,
+    cancelled: Arc<AtomicBool>,
+    progress: ProgressTracker,
+    sink: Arc<dyn EventSink>,
+}
+
+impl ImportContext {
+    fn new(
+        dest_dir: &Path,
+        project_id: String,
+        project_name: String,
+        options: ImportOptions,
+        import_rules: ImportRules,
+        is_bedrock: bool,
+        sink: Arc<dyn EventSink>,
+    ) -> Result<Self> {
+        Ok(ImportContext {
+            dest_dir: dest_dir.to_path_buf(),
+            progress: ProgressTracker::new(project_id.clone(), sink.clone()),
+            project_id,
+            project_name,
+            options,
+            is_bedrock,
+            rules: CompiledRules::new(&import_rules)?,
+            import_rules,
+            cancelled: Arc::default(),
+            sink,
+        })
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err(anyhow!("Import cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+enum FileOutcome {
+    Converted,
+    // Copied over as is, like models or pack.png
+    Copied,
+    Skipped(String),
+}
+
+const IGNORED: &str = "Ignored by a folder rule";
+
+// Forward slash path of a project file relative to the project root, as the
+// import globs expect it
+fn relative_path(dest_dir: &Path, path: &Path) -> String {
+    zip_path(path.strip_prefix(dest_dir).unwrap_or(path))
+}
+
+// Everything in the project folder before the import, so a cancelled import
+// can remove exactly what it added
+struct ProjectSnapshot {
+    paths: HashSet<PathBuf>,
+    project_yml: Option<Vec<u8>>,
+}
+
+impl ProjectSnapshot {
+    fn take(dir: &Path) -> Self {
+        let mut paths = HashSet::new();
+        let mut stack = vec![dir.to_path_buf()];
+
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path.clone());
+                }
+                paths.insert(path);
+            }
+        }
+
+        ProjectSnapshot {
+            paths,
+            project_yml: fs::read(dir.join("project.yml")).ok(),
+        }
+    }
+
+    fn restore(&self, dir: &Path) -> io::Result<()> {
+        let mut stack = vec![dir.to_path_buf()];
+
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(&dir)?.filter_map(Result::ok) {
+                let path = entry.path();
+                let is_dir = path.is_dir();
+
+                match (self.paths.contains(&path), is_dir) {
+                    (true, true) => stack.push(path),
+                    (true, false) => (),
+                    (false, true) => fs::remove_dir_all(&path)?,
+                    (false, false) => fs::remove_file(&path)?,
+                }
+            }
+        }
+
+        if let Some(project_yml) = &self.project_yml {
+            fs::write(dir.join("project.yml"), project_yml)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Registers the import so it can be cancelled, runs it and rolls the project
+// back if it doesn't finish
+fn run_import(
+    ctx: ImportContext,
+    extract: impl FnOnce(&ImportContext, &mut ImportReport) -> Result<Vec<PathBuf>>,
+) -> Result<ImportReport> {
+    let snapshot = ProjectSnapshot::take(&ctx.dest_dir);
+    start_import(&ctx.project_id, ctx.cancelled.clone());
+
+    let result = (|| {
+        let mut report = ImportReport::default();
+        let files = extract(&ctx, &mut report)?;
+        ctx.progress.set_stage(ImportStage::Converting);
+        process_files(&ctx, files, &mut report)?;
+        Ok(report)
+    })();
+
+    end_import(&ctx.project_id);
+
+    if result.is_err() {
+        if let Err(e) = snapshot.restore(&ctx.dest_dir) {
+            eprintln!("Failed to roll back import: {}", e);
+        }
+    }
+
+    ctx.progress
+        .finish(&result, ctx.cancelled.load(Ordering::Relaxed));
+    result
+}
+
+pub fn unzip_and_process(
+    archive_path: &Path,
+    dest_dir: &Path,
+    project_id: String,
+    project_name: String,
+    options: ImportOptions,
+    sink: Arc<dyn EventSink>,
+) -> Result<ImportReport> {
+    unzip_with_limits(
+        archive_path,
+        dest_dir,
+        project_id,
+        project_name,
+        options,
+        sink,
+        ARCHIVE_LIMITS,
+    )
+}
+
+fn unzip_with_limits(
+    archive_path: &Path,
+    dest_dir: &Path,
+    project_id: String,
+    project_name: String,
+    options: ImportOptions,
+    sink: Arc<dyn EventSink>,
+    limits: ArchiveLimits,
+) -> Result<ImportReport> {
+    let file = File::open(archive_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    if archive.len() > limits.entries {
+        return Err(anyhow!(
+            "The archive has {} entries, more than the {} allowed",
+            archive.len(),
+            limits.entries
+        ));
+    }
+
+    let mut declared_size = 0u64;
+    for i in 0..archive.len() {
+        declared_size = declared_size.saturating_add(archive.by_index_raw(i)?.size());
+    }
+    if declared_size > limits.size {
+        return Err(anyhow!(
+            "The archive unpacks to {} bytes, more than the {} allowed",
+            declared_size,
+            limits.size
+        ));
+    }
+
+    // Bedrock packs keep a manifest.json at the root, sometimes one folder down
+    let is_bedrock = archive.file_names().any(|name| {
+        name == "manifest.json"
+            || name.matches('/').count() == 1 && name.ends_with("/manifest.json")
+    });
+
+    let import_rules = load_import_rules(dest_dir);
+
+    let ctx = ImportContext::new(
+        dest_dir,
+        project_id,
+        project_name,
+        options,
+        import_rules,
+        is_bedrock,
+        sink,
+    )?;
+
+    run_import(ctx, |ctx, report| {
+        let mut files = Vec::new();
+        let mut written = 0u64;
+        ctx.progress.set_total_entries(archive.len());
+
+        for i in 0..archive.len() {
+            ctx.check_cancelled()?;
+
+            let mut entry = archive.by_index(i)?;
+            // Entries escaping the project folder are never written
+            let Some(rel_path) = entry.enclosed_name().map(Path::to_path_buf) else {
+                ctx.progress.extracted_failed(entry.name());
+                report.failed.push(ImportFailure {
+                    path: entry.name().to_string(),
+                    error: "Unsafe path in archive".to_string(),
+                });
+                continue;
+            };
+            let out_path = dest_dir.join(&rel_path);
+
+            if entry.is_dir() {
+                fs::create_dir_all(&out_path)?;
+                ctx.progress.extracted(&zip_path(&rel_path));
+                continue;
+            }
+            if ctx.rules.directory_behavior(&zip_path(&rel_path)) == DirectoryBehavior::Ignore {
+                ctx.progress.extracted_skipped(&zip_path(&rel_path));
+                report.skipped.push(ImportSkipped {
+                    path: zip_path(&rel_path),
+                    reason: IGNORED.to_string(),
+                });
+                continue;
+            }
+
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            // The declared sizes can lie, so the limit is enforced on
+            // what is actually written as well
+            let remaining = limits.size - written;
+            let mut out_file = File::create(&out_path)?;
+            written += io::copy(&mut (&mut entry).take(remaining + 1), &mut out_file)?;
+            if written > limits.size {
+                return Err(anyhow!(
+                    "The archive unpacks to more than the {} bytes allowed",
+                    limits.size
+                ));
+            }
+
+            ctx.progress.extracted(&zip_path(&rel_path));
+            files.push(out_path);
+        }
+
+        Ok(files)
+    })
+}
+
+// Same as `unzip_and_process`, but copies an unzipped pack folder. The source
+// folder is only ever read from.
+pub fn copy_and_process(
+    src_dir: &Path,
+    dest_dir: &Path,
+    project_id: String,
+    project_name: String,
+    options: ImportOptions,
+    sink: Arc<dyn EventSink>,
+) -> Result<ImportReport> {
+    let src_dir = fs::canonicalize(src_dir)?;
+    if fs::canonicalize(dest_dir)?.starts_with(&src_dir) {
+        return Err(anyhow!(
+            "The project folder can't be inside the pack being imported"
+        ));
+    }
+
+    let is_bedrock = src_dir.join("manifest.json").exists()
+        || fs::read_dir(&src_dir)?
+            .filter_map(Result::ok)
+            .any(|entry| entry.path().join("manifest.json").exists());
+
+    // Packs kept in git may carry their own project.yml with import rules
+    let import_rules = if src_dir.join("project.yml").exists() {
+        load_import_rules(&src_dir)
+    } else {
+        load_import_rules(dest_dir)
+    };
+
+    let ctx = ImportContext::new(
+        dest_dir,
+        project_id,
+        project_name,
+        options,
+        import_rules,
+        is_bedrock,
+        sink,
+    )?;
+
+    run_import(ctx, |ctx, report| {
+        let mut files = Vec::new();
+        let pack_files = find_pack_files(&src_dir);
+        ctx.progress.set_total_entries(pack_files.len());
+
+        for src_path in pack_files {
+            ctx.check_cancelled()?;
+
+            let rel_path = src_path.strip_prefix(&src_dir)?;
+            if ctx.rules.directory_behavior(&zip_path(rel_path)) == DirectoryBehavior::Ignore {
+                ctx.progress.extracted_skipped(&zip_path(rel_path));
+                report.skipped.push(ImportSkipped {
+                    path: zip_path(rel_path),
+                    reason: IGNORED.to_string(),
+                });
+                continue;
+            }
+
+            let out_path = dest_dir.join(rel_path);
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&src_path, &out_path)?;
+
+            ctx.progress.extracted(&zip_path(rel_path));
+            files.push(out_path);
+        }
+
+        Ok(files)
+    })
+}
+
+// Every file in a pack folder, skipping hidden files and folders such as .git
+fn find_pack_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    files
+}
+
+// Converts the files on the rayon pool, then the Bedrock texture sets once
+// everything is on disk, and writes the import report into the project
+fn process_files(
+    ctx: &ImportContext,
+    files: Vec<PathBuf>,
+    report: &mut ImportReport,
+) -> Result<()> {
+    let outcomes: Vec<(PathBuf, Result<FileOutcome>)> = files
+        .into_par_iter()
+        .map(|out_path| {
+            let outcome = ctx
+                .check_cancelled()
+                .and_then(|_| process_file(&out_path, ctx));
+
+            let path = relative_path(&ctx.dest_dir, &out_path);
+            match &outcome {
+                Ok(FileOutcome::Converted) => ctx.progress.decoded(&path),
+                Ok(FileOutcome::Copied) => ctx.progress.copied(&path),
+                Ok(FileOutcome::Skipped(_)) => ctx.progress.skipped(&path),
+                Err(_) => ctx.progress.failed(&path),
+            }
+            (out_path, outcome)
+        })
+        .collect();
+    ctx.check_cancelled()?;
+
+    for (out_path, outcome) in outcomes {
+        let path = relative_path(&ctx.dest_dir, &out_path);
+
+        match outcome {
+            Ok(FileOutcome::Converted) => report.converted.push(path),
+            Ok(FileOutcome::Copied) => (),
+            Ok(FileOutcome::Skipped(reason)) => report.skipped.push(ImportSkipped { path, reason }),
+            Err(e) => {
+                eprintln!("Failed to import {}: {}", path, e);
+                report.failed.push(ImportFailure {
+                    path,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    if ctx.is_bedrock {
+        ctx.progress.set_stage(ImportStage::TextureSets);
+        let texture_sets = import_texture_sets(
+            &ctx.dest_dir,
+            &ctx.rules,
+            ctx.options.keep_originals,
+            &ctx.progress,
+        )?;
+        report.merge(texture_sets);
+        ctx.check_cancelled()?;
+    }
+
+    ctx.progress.set_stage(ImportStage::Finishing);
+
+    let report_path = ctx.dest_dir.join(IMPORT_REPORT_PATH);
+    if let Some(parent) = report_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(report_path, serde_json::to_string_pretty(&report)?)?;
+
+    Ok(())
+}
+
+// Registered projects also get their entry in projects.yml updated, others
+// only have their project.yml written
+fn save_project_yml(ctx: &ImportContext, project_yml: ProjectYml) -> Result<()> {
+    match update_project(ctx.project_id.clone(), &project_yml, ctx.sink.as_ref()) {
+        Err(Error::NotFound { .. }) => write_project_yml(&ctx.dest_dir, &project_yml)?,
+        result => result?,
+    }
+    Ok(())
+}
+
+fn process_file(out_path: &Path, ctx: &ImportContext) -> Result<FileOutcome> {
+    let project_name = ctx.project_name.clone();
+
+    if ctx.is_bedrock {
+        if out_path.file_name() == Some(std::ffi::OsStr::new("manifest.json")) {
+            let manifest_str = fs::read_to_string(out_path)?;
+            let manifest: BedrockManifest = serde_json::from_str(&manifest_str)
+                .map_err(|e| anyhow!("Malformed manifest.json: {}", e))?;
+
+            let name = match manifest.header.name {
+                Some(name) if project_name == "My New Project" => name,
+                _ => project_name,
+            };
+
+            let new_project_yml = ProjectYml {
+                import: Some(ctx.import_rules.clone()),
+                ..ProjectYml::new(name, manifest.header.description, InputFormat::Raw)
+            };
+            save_project_yml(ctx, new_project_yml)?;
+        }
+
+        // Texture sets are converted by `import_texture_sets` once every file is copied
+        return Ok(FileOutcome::Copied);
+    }
+
+    if out_path.to_string_lossy().contains("pack.mcmeta") {
+        let mc_meta_str = fs::read_to_string(out_path)?;
+
+        let mc_meta: McMeta = serde_json::from_str(&mc_meta_str)
+            .map_err(|e| anyhow!("Malformed pack.mcmeta: {}", e))?;
+        let description = mc_meta.pack.description_text();
+
+        let mut name = mc_meta.pack.name.unwrap_or(project_name.clone());
+        if name != "My New Project" {
+            name = project_name;
+        }
+
+        let new_project_yml = ProjectYml {
+            import: Some(ctx.import_rules.clone()),
+            ..ProjectYml::new(name, Some(description), ctx.options.format)
+        };
+        save_project_yml(ctx, new_project_yml)?;
+    }
+
+    if ctx
+        .rules
+        .directory_behavior(&relative_path(&ctx.dest_dir, out_path))
+        == DirectoryBehavior::Keep
+    {
+        return Ok(FileOutcome::Copied);
+    }
+
+    if let Some(ext) = out_path.extension() {
+        if out_path.file_name() != Some(std::ffi::OsStr::new("pack.png")) && ext == "png" {
+            return process_image(out_path, ctx);
+        }
+    }
+
+    Ok(FileOutcome::Copied)
+}
+
+// Decodes one texture into its material folder. The packed file is only
+// retired once it has been decoded, so a failure leaves it where it was.
+fn process_image(image_path: &Path, ctx: &ImportContext) -> Result<FileOutcome> {
+    let options = ctx.options;
+    let dest_dir = &ctx.dest_dir;
+
+    if let Some(reason) = ctx.rules.skip_reason(&relative_path(dest_dir, image_path)) {
+        return Ok(FileOutcome::Skipped(reason));
+    }
+
+    let stem = image_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let (name, channel) = ctx.rules.split_suffix(&stem);
+    let material_dir = image_path.with_file_name(name);
+
+    fs::create_dir_all(&material_dir)?;
+    if !material_dir.join("mat.yml").exists() {
+        fs::write(
+            material_dir.join("mat.yml"),
+            serde_yaml::to_string(&MatYml::new())?,
+        )?;
+    }
+
+    match channel.unwrap_or("color") {
+        "normal" => {
+            save_packed_normal(&material_dir, image_path, options.format)
+                .map_err(|e| anyhow!("Failed to decode normal texture: {}", e))?;
+
+            retire_original(dest_dir, image_path, options.keep_originals)?;
+        }
+        "specular" => {
+            save_packed_specular(&material_dir, image_path, options.format)
+                .map_err(|e| anyhow!("Failed to decode specular texture: {}", e))?;
+
+            retire_original(dest_dir, image_path, options.keep_originals)?;
+        }
+        "color" => {
+            if options.keep_originals {
+                let backup = originals_path(dest_dir, image_path);
+                if let Some(parent) = backup.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(image_path, backup)?;
+            }
+
+            fs::rename(image_path, material_dir.join("color.png"))?;
+            save_channel_map(
+                &material_dir,
+                3,
+                &material_dir.join("color.png"),
+                String::from("opacity.png"),
+                false,
+            )
+            .map_err(|e| anyhow!("Failed to save opacity: {}", e))?;
+        }
+        channel => {
+            save_channel_file(image_path, &material_dir, channel)?;
+
+            retire_original(dest_dir, image_path, options.keep_originals)?;
+        }
+    }
+
+    Ok(FileOutcome::Converted)
+}
+
+// A texture that holds a single channel, e.g. an OptiFine `_e` emissive map
+fn save_channel_file(image_path: &Path, material_dir: &Path, channel: &str) -> Result<()> {
+    let texture_file = TEXTURE_FILES
+        .iter()
+        .find(|t| t.name == channel)
+        .ok_or_else(|| anyhow!("Unknown channel in import suffixes: {}", channel))?;
+
+    let img = image::open(image_path)?;
+    let out_path = material_dir.join(format!("{}.png", texture_file.name));
+    if texture_file.grayscale {
+        img.to_luma8().save(out_path)?;
+    } else {
+        img.save(out_path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        events::{HeadlessSink, RecordingSink},
+        import::{cancel_import, progress::FINISHED_EVENT, ORIGINALS_DIR},
+    };
+    use image::{Rgba, RgbaImage};
+    use std::collections::BTreeMap;
+
+    const BLOCKS: &str = "assets/minecraft/textures/block";
+
+    // Every file under `dir` with its contents, hidden ones included
+    fn tree(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut files = BTreeMap::new();
+        let mut stack = vec![dir.to_path_buf()];
+
+        while let Some(current) = stack.pop() {
+            for entry in fs::read_dir(&current).unwrap().flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path);
+                } else {
+                    let contents = fs::read(&path).unwrap();
+                    files.insert(path.strip_prefix(dir).unwrap().to_path_buf(), contents);
+                }
+            }
+        }
+
+        files
+    }
+
+    fn write(path: &Path, contents: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn png(path: &Path, pixel: [u8; 4]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        RgbaImage::from_pixel(2, 2, Rgba(pixel)).save(path).unwrap();
+    }
+
+    fn java_pack(dir: &Path) {
+        write(
+            &dir.join("pack.mcmeta"),
+            br#"{"pack":{"pack_format":34,"description":"Test"}}"#,
+        );
+        png(&dir.join("pack.png"), [1, 2, 3, 255]);
+        png(&dir.join(BLOCKS).join("stone.png"), [100, 100, 100, 255]);
+        png(&dir.join(BLOCKS).join("stone_n.png"), [128, 128, 40, 200]);
+        png(&dir.join(BLOCKS).join("stone_s.png"), [90, 20, 255, 254]);
+        write(
+            &dir.join("assets/minecraft/models/block/stone.json"),
+            br#"{"parent":"block/cube_all"}"#,
+        );
+        write(&dir.join(".git/HEAD"), b"ref: refs/heads/main\n");
+    }
+
+    fn copy(src: &Path, dest: &Path, options: ImportOptions) -> Result<ImportReport> {
+        copy_and_process(
+            src,
+            dest,
+            "test".to_string(),
+            "My New Project".to_string(),
+            options,
+            Arc::new(HeadlessSink::default()),
+        )
+    }
+
+    #[test]
+    fn copy_leaves_source_untouched() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        java_pack(src.path());
+        let before = tree(src.path());
+
+        let report = copy(
+            src.path(),
+            dest.path(),
+            ImportOptions {
+                keep_originals: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(tree(src.path()), before);
+        assert!(report.failed.is_empty());
+        assert!(dest.path().join(BLOCKS).join("stone/color.png").is_file());
+        assert!(!dest.path().join(".git").exists());
+    }
+
+    #[test]
+    fn failed_textures_keep_their_source() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        java_pack(src.path());
+        write(&src.path().join(BLOCKS).join("dirt_n.png"), b"not a png");
+
+        let report = copy(
+            src.path(),
+            dest.path(),
+            ImportOptions {
+                keep_originals: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let blocks = dest.path().join(BLOCKS);
+        let originals = dest.path().join(ORIGINALS_DIR).join(BLOCKS);
+        for decoded in ["stone.png", "stone_n.png", "stone_s.png"] {
+            assert!(originals.join(decoded).is_file(), "{decoded}");
+        }
+        assert!(!blocks.join("stone_n.png").exists());
+        assert!(!blocks.join("stone_s.png").exists());
+
+        // The broken texture stays where it was and isn't backed up
+        assert_eq!(fs::read(blocks.join("dirt_n.png")).unwrap(), b"not a png");
+        assert!(!originals.join("dirt_n.png").exists());
+        let failed: Vec<_> = report.failed.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(failed, vec!["assets/minecraft/textures/block/dirt_n.png"]);
+
+        let written: serde_json::Value =
+            serde_json::from_slice(&fs::read(dest.path().join(IMPORT_REPORT_PATH)).unwrap())
+                .unwrap();
+        assert_eq!(written, serde_json::to_value(&report).unwrap());
+        assert_eq!(written["failed"][0]["path"], failed[0]);
+    }
+
+    fn zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, contents) in entries {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            io::Write::write_all(&mut zip, contents).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn unzip(archive: &Path, dest: &Path, limits: ArchiveLimits) -> Result<ImportReport> {
+        unzip_with_limits(
+            archive,
+            dest,
+            "test".to_string(),
+            "My New Project".to_string(),
+            ImportOptions::default(),
+            Arc::new(HeadlessSink::default()),
+            limits,
+        )
+    }
+
+    #[test]
+    fn escaping_entries_are_not_written() {
+        let root = tempfile::tempdir().unwrap();
+        let dest = root.path().join("project");
+        fs::create_dir(&dest).unwrap();
+        zip(
+            &root.path().join("pack.zip"),
+            &[
+                ("../evil.txt", b"evil"),
+                ("/tmp/absolute.txt", b"evil"),
+                ("readme.txt", b"fine"),
+            ],
+        );
+
+        let report = unzip(&root.path().join("pack.zip"), &dest, ARCHIVE_LIMITS).unwrap();
+
+        assert!(!root.path().join("evil.txt").exists());
+        assert_eq!(fs::read(dest.join("readme.txt")).unwrap(), b"fine");
+        let failed: Vec<_> = report
+            .failed
+            .iter()
+            .map(|f| (f.path.as_str(), f.error.as_str()))
+            .collect();
+        assert!(failed.contains(&("../evil.txt", "Unsafe path in archive")));
+    }
+
+    #[test]
+    fn archive_limits() {
+        let root = tempfile::tempdir().unwrap();
+        let dest = root.path().join("project");
+        fs::create_dir(&dest).unwrap();
+        let archive = root.path().join("pack.zip");
+        zip(
+            &archive,
+            &[("a.txt", &[0; 60]), ("b.txt", &[0; 60]), ("c.txt", b"")],
+        );
+
+        let too_many = unzip(
+            &archive,
+            &dest,
+            ArchiveLimits {
+                entries: 2,
+                size: 1000,
+            },
+        );
+        assert_eq!(
+            too_many.err().unwrap().to_string(),
+            "The archive has 3 entries, more than the 2 allowed"
+        );
+
+        let too_big = unzip(
+            &archive,
+            &dest,
+            ArchiveLimits {
+                entries: 3,
+                size: 100,
+            },
+        );
+        assert_eq!(
+            too_big.err().unwrap().to_string(),
+            "The archive unpacks to 120 bytes, more than the 100 allowed"
+        );
+
+        // Nothing is written before the limits are checked
+        assert_eq!(fs::read_dir(&dest).unwrap().count(), 0);
+        unzip(
+            &archive,
+            &dest,
+            ArchiveLimits {
+                entries: 3,
+                size: 120,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn malformed_pack_files_fail_the_file() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(&src.path().join("pack.mcmeta"), b"{\"pack\": ");
+        let report = copy(src.path(), dest.path(), ImportOptions::default()).unwrap();
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, "pack.mcmeta");
+        assert!(report.failed[0].error.starts_with("Malformed pack.mcmeta"));
+
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(&src.path().join("manifest.json"), b"{\"header\": 3}");
+        let report = copy(src.path(), dest.path(), ImportOptions::default()).unwrap();
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, "manifest.json");
+        assert!(report.failed[0]
+            .error
+            .starts_with("Malformed manifest.json"));
+    }
+
+    #[test]
+    fn cancelling_restores_the_project() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(
+            &src.path().join("manifest.json"),
+            br#"{"header": {"name": "RTX", "description": "Shiny"}}"#,
+        );
+        png(&src.path().join("textures/blocks/ore.png"), [1, 2, 3, 255]);
+        write(
+            &src.path().join("textures/blocks/ore.texture_set.json"),
+            br#"{"minecraft:texture_set": {"color": "ore", "metalness_emissive_roughness": [0, 0, 255]}}"#,
+        );
+
+        write(&dest.path().join("project.yml"), b"# mine\nname: Mine\n");
+        write(&dest.path().join("notes/todo.txt"), b"todo");
+        let before = tree(dest.path());
+
+        // The texture sets are converted after project.yml has been replaced
+        let sink = RecordingSink {
+            on_emit: Some(Box::new(|_, payload| {
+                if payload["stage"] == "texture-sets" {
+                    cancel_import(Some("cancel-test".to_string()));
+                }
+            })),
+            ..Default::default()
+        };
+        let sink = Arc::new(sink);
+        let result = copy_and_process(
+            src.path(),
+            dest.path(),
+            "cancel-test".to_string(),
+            "My New Project".to_string(),
+            ImportOptions::default(),
+            sink.clone(),
+        );
+
+        assert_eq!(result.err().unwrap().to_string(), "Import cancelled");
+        assert_eq!(tree(dest.path()), before);
+        assert!(!dest.path().join("textures").exists());
+        assert_eq!(sink.events(FINISHED_EVENT)[0]["cancelled"], true);
+    }
+}
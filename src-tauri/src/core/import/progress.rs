@@ -0,0 +1,202 @@
+// Progress of a running import. The frontend gets `import-progress` events
+// while it runs, at most one per EMIT_INTERVAL, and a single
+// `import-finished` event with the summary once it is done.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use super::ImportReport;
+use crate::core::events::EventSink;
+
+pub const PROGRESS_EVENT: &str = "import-progress";
+pub const FINISHED_EVENT: &str = "import-finished";
+
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportStage {
+    // Unzipping or copying the pack into the project
+    Extracting,
+    // Decoding textures into material folders
+    Converting,
+    // Bedrock only, texture sets are decoded once every file is on disk
+    TextureSets,
+    // Writing the import report
+    Finishing,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct ImportProgress {
+    pub project_id: String,
+    pub stage: ImportStage,
+    // Entries in the archive or files in the pack folder
+    pub total_entries: usize,
+    pub extracted: usize,
+    // Files that went through conversion, whatever came out of it
+    pub processed: usize,
+    pub decoded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub current: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct ImportSummary<'a> {
+    pub project_id: &'a str,
+    pub success: bool,
+    pub cancelled: bool,
+    pub error: Option<String>,
+    pub decoded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub elapsed_ms: u64,
+    pub report: Option<&'a ImportReport>,
+}
+
+pub struct ProgressTracker {
+    project_id: String,
+    sink: Arc<dyn EventSink>,
+    started: Instant,
+    stage: Mutex<ImportStage>,
+    last_emit: Mutex<Option<Instant>>,
+    total_entries: AtomicUsize,
+    extracted: AtomicUsize,
+    processed: AtomicUsize,
+    decoded: AtomicUsize,
+    skipped: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+impl ProgressTracker {
+    pub fn new(project_id: String, sink: Arc<dyn EventSink>) -> Self {
+        ProgressTracker {
+            project_id,
+            sink,
+            started: Instant::now(),
+            stage: Mutex::new(ImportStage::Extracting),
+            last_emit: Mutex::new(None),
+            total_entries: AtomicUsize::new(0),
+            extracted: AtomicUsize::new(0),
+            processed: AtomicUsize::new(0),
+            decoded: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn set_total_entries(&self, total: usize) {
+        self.total_entries.store(total, Ordering::Relaxed);
+        self.emit(None);
+    }
+
+    // Stage changes are always sent, whatever the interval
+    pub fn set_stage(&self, stage: ImportStage) {
+        if let Ok(mut current) = self.stage.lock() {
+            *current = stage;
+        }
+        self.emit(None);
+    }
+
+    pub fn extracted(&self, path: &str) {
+        self.extracted.fetch_add(1, Ordering::Relaxed);
+        self.emit_throttled(path);
+    }
+
+    // Entries left out while extracting still count towards the total
+    pub fn extracted_skipped(&self, path: &str) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+        self.extracted(path);
+    }
+
+    pub fn extracted_failed(&self, path: &str) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        self.extracted(path);
+    }
+
+    pub fn copied(&self, path: &str) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        self.emit_throttled(path);
+    }
+
+    pub fn decoded(&self, path: &str) {
+        self.decoded.fetch_add(1, Ordering::Relaxed);
+        self.copied(path);
+    }
+
+    pub fn skipped(&self, path: &str) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+        self.copied(path);
+    }
+
+    pub fn failed(&self, path: &str) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        self.copied(path);
+    }
+
+    pub fn snapshot(&self, current: Option<&str>) -> ImportProgress {
+        ImportProgress {
+            project_id: self.project_id.clone(),
+            stage: self.stage.lock().map_or(ImportStage::Extracting, |s| *s),
+            total_entries: self.total_entries.load(Ordering::Relaxed),
+            extracted: self.extracted.load(Ordering::Relaxed),
+            processed: self.processed.load(Ordering::Relaxed),
+            decoded: self.decoded.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            current: current.map(str::to_string),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        }
+    }
+
+    fn send(&self, event: &str, payload: impl serde::Serialize) {
+        match serde_json::to_value(payload) {
+            Ok(payload) => self.sink.emit(event, payload),
+            Err(e) => eprintln!("Failed to serialize {}: {}", event, e),
+        }
+    }
+
+    fn emit(&self, current: Option<&str>) {
+        if let Ok(mut last_emit) = self.last_emit.lock() {
+            *last_emit = Some(Instant::now());
+        }
+        self.send(PROGRESS_EVENT, self.snapshot(current));
+    }
+
+    // Worker threads that find another one emitting just carry on
+    fn emit_throttled(&self, current: &str) {
+        let Ok(mut last_emit) = self.last_emit.try_lock() else {
+            return;
+        };
+        if last_emit.is_some_and(|last| last.elapsed() < EMIT_INTERVAL) {
+            return;
+        }
+        *last_emit = Some(Instant::now());
+        drop(last_emit);
+
+        self.send(PROGRESS_EVENT, self.snapshot(Some(current)));
+    }
+
+    pub fn finish(&self, result: &anyhow::Result<ImportReport>, cancelled: bool) {
+        let progress = self.snapshot(None);
+        let summary = ImportSummary {
+            project_id: &self.project_id,
+            success: result.is_ok(),
+            cancelled,
+            error: result.as_ref().err().map(|e| e.to_string()),
+            decoded: progress.decoded,
+            skipped: progress.skipped,
+            failed: progress.failed,
+            elapsed_ms: progress.elapsed_ms,
+            report: result.as_ref().ok(),
+        };
+
+        self.send(FINISHED_EVENT, summary);
+    }
+}
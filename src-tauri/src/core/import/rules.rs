@@ -0,0 +1,248 @@
+// Compiled form of the `import` section in project.yml
+
+use anyhow::{anyhow, Result};
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+
+use crate::core::project::{
+    load_project_yml,
+    structs::{DirectoryBehavior, ImportRules},
+};
+
+// `*` stays within a folder, `**` crosses them
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+pub struct CompiledRules {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    // Longest first, so `_glow_e` is tried before `_e`
+    suffixes: Vec<(String, String)>,
+    directories: Vec<(Pattern, DirectoryBehavior)>,
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|p| Pattern::new(p).map_err(|e| anyhow!("Invalid import glob {}: {}", p, e)))
+        .collect()
+}
+
+impl CompiledRules {
+    pub fn new(rules: &ImportRules) -> Result<Self> {
+        let mut suffixes: Vec<(String, String)> = rules
+            .suffixes
+            .iter()
+            .map(|(suffix, channel)| (suffix.clone(), channel.clone()))
+            .collect();
+        suffixes.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(&b.0)));
+
+        let directories = rules
+            .directories
+            .iter()
+            .map(|rule| {
+                Pattern::new(&rule.path)
+                    .map(|pattern| (pattern, rule.behavior))
+                    .map_err(|e| anyhow!("Invalid import glob {}: {}", rule.path, e))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(CompiledRules {
+            include: compile(&rules.include)?,
+            exclude: compile(&rules.exclude)?,
+            suffixes,
+            directories,
+        })
+    }
+
+    // What happens to a file, decided by the first folder rule matching any
+    // of the folders it sits in. `rel_path` uses forward slashes.
+    pub fn directory_behavior(&self, rel_path: &str) -> DirectoryBehavior {
+        let folders: Vec<&str> = rel_path
+            .char_indices()
+            .filter(|(_, c)| *c == '/')
+            .map(|(i, _)| &rel_path[..i])
+            .collect();
+
+        self.directories
+            .iter()
+            .find(|(pattern, _)| {
+                folders
+                    .iter()
+                    .any(|folder| pattern.matches_with(folder, MATCH_OPTIONS))
+            })
+            .map_or(DirectoryBehavior::Convert, |(_, behavior)| *behavior)
+    }
+
+    // Why a texture is copied as is instead of converted, if it is
+    pub fn skip_reason(&self, rel_path: &str) -> Option<String> {
+        if !self.include.is_empty()
+            && !self
+                .include
+                .iter()
+                .any(|p| p.matches_with(rel_path, MATCH_OPTIONS))
+        {
+            return Some("Not matched by any include rule".to_string());
+        }
+
+        self.exclude
+            .iter()
+            .find(|p| p.matches_with(rel_path, MATCH_OPTIONS))
+            .map(|p| format!("Excluded by {}", p.as_str()))
+    }
+
+    // Splits a file stem into the material name and the channel its suffix
+    // maps to, e.g. `stone_n` -> (`stone`, Some(`normal`))
+    pub fn split_suffix<'a>(&self, stem: &'a str) -> (&'a str, Option<&str>) {
+        self.suffixes
+            .iter()
+            .find_map(|(suffix, channel)| {
+                stem.strip_suffix(suffix.as_str())
+                    .filter(|name| !name.is_empty())
+                    .map(|name| (name, Some(channel.as_str())))
+            })
+            .unwrap_or((stem, None))
+    }
+}
+
+// The rules from a project.yml, or the defaults when it has none
+pub fn load_import_rules(project_dir: &Path) -> ImportRules {
+    load_project_yml(project_dir)
+        .ok()
+        .and_then(|project_yml| project_yml.import)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::project::structs::DirectoryRule;
+
+    fn rules(edit: impl FnOnce(&mut ImportRules)) -> CompiledRules {
+        let mut rules = ImportRules::default();
+        edit(&mut rules);
+        CompiledRules::new(&rules).unwrap()
+    }
+
+    fn directory(path: &str, behavior: DirectoryBehavior) -> DirectoryRule {
+        DirectoryRule {
+            path: path.to_string(),
+            behavior,
+        }
+    }
+
+    #[test]
+    fn first_folder_rule_wins() {
+        let rules = rules(|rules| {
+            rules.directories = vec![
+                directory("**/textures/block/ores", DirectoryBehavior::Keep),
+                directory("**/textures/block", DirectoryBehavior::Ignore),
+                directory("**/optifine", DirectoryBehavior::Keep),
+            ];
+        });
+
+        let behavior = |path| rules.directory_behavior(path);
+        assert_eq!(
+            behavior("assets/minecraft/textures/block/ores/gold.png"),
+            DirectoryBehavior::Keep
+        );
+        assert_eq!(
+            behavior("assets/minecraft/textures/block/stone.png"),
+            DirectoryBehavior::Ignore
+        );
+        // Any folder a file sits in counts, not just its own
+        assert_eq!(
+            behavior("assets/minecraft/optifine/ctm/glass/1.png"),
+            DirectoryBehavior::Keep
+        );
+        assert_eq!(
+            behavior("assets/minecraft/textures/item/apple.png"),
+            DirectoryBehavior::Convert
+        );
+        assert_eq!(behavior("pack.png"), DirectoryBehavior::Convert);
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        let defaults = rules(|_| ());
+        assert_eq!(
+            defaults.skip_reason("assets/minecraft/textures/gui/widgets.png"),
+            Some("Excluded by **/textures/gui/**".to_string())
+        );
+        assert_eq!(
+            defaults.skip_reason("assets/minecraft/textures/block/stone.png"),
+            None
+        );
+
+        let rules = rules(|rules| {
+            rules.include = vec!["**/textures/block/*".to_string()];
+            rules.exclude = vec!["**/block/*_glass*".to_string()];
+        });
+        assert_eq!(
+            rules.skip_reason("assets/minecraft/textures/item/apple.png"),
+            Some("Not matched by any include rule".to_string())
+        );
+        // `*` doesn't cross folders
+        assert!(rules
+            .skip_reason("assets/minecraft/textures/block/ores/gold.png")
+            .is_some());
+        // Exclusion beats inclusion, case is ignored
+        assert_eq!(
+            rules.skip_reason("assets/minecraft/textures/block/RED_GLASS.png"),
+            Some("Excluded by **/block/*_glass*".to_string())
+        );
+        assert_eq!(
+            rules.skip_reason("assets/minecraft/textures/block/stone.png"),
+            None
+        );
+    }
+
+    #[test]
+    fn longest_suffix_first() {
+        let rules = rules(|rules| {
+            for (suffix, channel) in [
+                ("_e", "emissive"),
+                ("_glow_e", "sss"),
+                ("_normal", "normal"),
+                ("_al_n", "ao"),
+            ] {
+                rules
+                    .suffixes
+                    .insert(suffix.to_string(), channel.to_string());
+            }
+        });
+
+        assert_eq!(rules.split_suffix("lamp_glow_e"), ("lamp", Some("sss")));
+        assert_eq!(rules.split_suffix("lamp_e"), ("lamp", Some("emissive")));
+        assert_eq!(
+            rules.split_suffix("stone_normal"),
+            ("stone", Some("normal"))
+        );
+        assert_eq!(rules.split_suffix("stone_n"), ("stone", Some("normal")));
+        assert_eq!(rules.split_suffix("stone_al_n"), ("stone", Some("ao")));
+        assert_eq!(rules.split_suffix("stone"), ("stone", None));
+        // A bare suffix is a name, not an empty material
+        assert_eq!(rules.split_suffix("_n"), ("_n", None));
+    }
+
+    #[test]
+    fn invalid_globs_are_rejected() {
+        let error = |edit: fn(&mut ImportRules)| {
+            let mut rules = ImportRules::default();
+            edit(&mut rules);
+            CompiledRules::new(&rules).err().unwrap().to_string()
+        };
+
+        assert!(error(|rules| rules.include = vec!["[block".to_string()])
+            .starts_with("Invalid import glob [block"));
+        assert!(error(|rules| rules.exclude.push("**/a**b".to_string()))
+            .starts_with("Invalid import glob **/a**b"));
+        assert!(
+            error(|rules| rules.directories = vec![directory("gui[", DirectoryBehavior::Keep)])
+                .starts_with("Invalid import glob gui[")
+        );
+    }
+}
@@ -1,12 +1,17 @@
 pub mod normal;
 pub mod structs;
 use normal::{apply_curved_normals, generate_normal_map};
-use structs::{Defaults, DefaultsGrayscale, MatYml, Normal, PngImage, TextureFile, TEXTURE_FILES};
+use structs::{
+    Defaults, DefaultsColor, DefaultsGrayscale, DefaultsHeight, Hcm, MatYml, PngImage, TextureFile,
+    TEXTURE_FILES,
+};
 
 use base64::{engine::general_purpose, Engine};
+use image::{DynamicImage, GrayImage};
 use rayon::prelude::*;
 
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
     io::Cursor,
     path::Path,
@@ -15,27 +20,27 @@ use std::{
 
 use tauri::Emitter;
 
-use super::normal_map::KernelSize;
+use super::{
+    codec::{decode_packed, find_source_file},
+    error::{CommandResult, Error},
+    metals::{metal_coverage, MetalCoverage},
+    migration::{migrate_mat_yml, parse_mat_yml, MAT_YML_VERSION},
+    normal_map::KernelSize,
+    project::{get_input_format, structs::InputFormat},
+    store::{self, FileLock},
+    template,
+    yaml_patch::{self, PatchError},
+};
 
 #[tauri::command]
-pub fn select_texture(material_path: String, app: tauri::AppHandle) -> Result<String, String> {
+pub fn select_texture(material_path: String, app: tauri::AppHandle) -> CommandResult<String> {
     let path = Path::new(&material_path);
     if !path.exists() {
-        return Err(String::from("Selected path does not exist."));
+        return Err(Error::not_found(path, "Selected path does not exist"));
     }
 
-    let mat_yml = fs::read_to_string(path)
-        .map_err(|e| {
-            println!("Failed to read mat.yml: {}", e);
-            return format!("Failed to read mat.yml: {}", e).to_string();
-        })
-        .unwrap();
-    let _: MatYml = serde_yaml::from_str(&mat_yml)
-        .map_err(|e| {
-            println!("Failed to serialise mat.yml: {}", e);
-            return format!("Failed to serialise mat.yml: {}", e).to_string();
-        })
-        .unwrap();
+    let mat_yml = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+    parse_mat_yml(path, &mat_yml)?;
 
     let _ = app.emit(
         "selected-texture",
@@ -50,64 +55,63 @@ pub fn select_texture_file(
     material_path: String,
     texture: String,
     app: tauri::AppHandle,
-) -> Result<String, String> {
+) -> CommandResult<String> {
     let path = Path::new(&material_path);
     if !path.exists() {
-        return Err(String::from("Selected path does not exist."));
+        return Err(Error::not_found(path, "Selected path does not exist"));
     }
 
     let texture_file = TEXTURE_FILES
         .iter()
         .find(|t| t.name == texture)
-        .ok_or_else(|| {
-            format!(
-                "Failed to find any texture file with that name: {}",
-                texture
-            )
-        })?;
+        .ok_or_else(|| Error::invalid_input(path, format!("Unknown texture: {}", texture)))?;
 
     // For mutlithreading process of data
     let mat_yml: Arc<MatYml> = Arc::new(load_mat_yml(path)?);
 
-    let base64_img = process_image(path, texture_file, mat_yml.clone())?;
+    let format = get_input_format(path);
+    let base64_img = process_image(path, texture_file, mat_yml.clone(), format)?;
 
     app.emit("selected-texture-file", base64_img.clone())
-        .map_err(|e| format!("Failed to emit event: {}", e))?;
+        .map_err(|e| Error::other(path, e))?;
 
     let result = match texture_file.name {
-        "opacity" => {
+        "color" => {
+            let res = mat_yml.color.clone().unwrap_or_default();
+
+            serde_json::to_string(&res).map_err(|e| Error::other(path, e))
+        }
+        "height" => {
+            let res = mat_yml.height.clone().unwrap_or_default();
+
+            serde_json::to_string(&res).map_err(|e| Error::other(path, e))
+        }
+        "hcm" => {
+            let res = mat_yml.hcm.clone().unwrap_or_default();
+
+            serde_json::to_string(&res).map_err(|e| Error::other(path, e))
+        }
+        name if GRAYSCALE_SECTIONS.contains(&name) => {
             let texture_properties = get_texture_properties_grayscale(&texture_file.name, &mat_yml);
 
             let res = texture_properties.unwrap_or(DefaultsGrayscale {
+                invert: Some(false),
                 value: Some(0.0),
                 shift: Some(0.0),
                 scale: Some(1.0),
             });
 
-            serde_json::to_string(&res).map_err(|e| {
-                let err = format!("Failed to serialize extended grayscale: {}", e);
-                eprintln!("{}", err);
-                err
-            })
+            serde_json::to_string(&res).map_err(|e| Error::other(path, e))
         }
         "normal" => {
-            let res = mat_yml.normal.clone().unwrap_or(Normal {
-                curve_x: Some(0.0),
-                curve_y: Some(0.0),
-                radius_size_x: Some(0.5),
-                radius_size_y: Some(0.5),
-                noise_angle: Some(0.0),
-                method: 0,
-                strength: Some(1.0),
-            });
+            let res = mat_yml.normal.clone().unwrap_or_default();
 
-            serde_json::to_string(&res).map_err(|e| {
-                let err = format!("Failed to serialize extended grayscale: {}", e);
-                eprintln!("{}", err);
-                err
-            })
+            serde_json::to_string(&res).map_err(|e| Error::other(path, e))
         }
-        _ => Err(String::from("Doesn't exist")),
+        _ => Err(Error::invalid_input(
+            path,
+            format!("{} has no settings to show", texture_file.name),
+        )),
     };
 
     result
@@ -117,39 +121,46 @@ fn process_image(
     path: &Path,
     texture_file: &TextureFile,
     mat_yml: Arc<MatYml>,
-) -> Result<String, String> {
-    let matching_file = find_matching_file(path, &texture_file.pattern);
+    format: InputFormat,
+) -> CommandResult<String> {
+    let matching_file = find_source_file(path, texture_file.pattern, format);
     let (mut img, original_exists) = match &matching_file {
-        Some(file) => (read_png_file(&file.path())?, true),
-        None => (create_default_image(texture_file), false),
+        // Colour adjustments work on 8 bit RGBA, whatever the file is stored as
+        Some(file)
+            if texture_file.name == "color"
+                && (mat_yml.color.is_some() || mat_yml.hcm.is_some()) =>
+        {
+            let decoded = image::open(file).map_err(|e| Error::parse(file, e))?;
+            (dynamic_to_png(decoded.to_rgba8().into()), true)
+        }
+        Some(file) => (read_png_file(file)?, true),
+        None => match decode_packed(path, format).remove(texture_file.name) {
+            Some(decoded) => (dynamic_to_png(decoded), true),
+            None => (create_default_image(texture_file), false),
+        },
     };
 
-    let processed_img = if texture_file.grayscale {
+    let processed_img = if texture_file.name == "height" && mat_yml.height.is_some() {
+        let size = (img.info.width as u32, img.info.height as u32);
+        match prepare_height(find_height(path, format)?, &mat_yml, size) {
+            Some(height) => dynamic_to_png(height),
+            None => img,
+        }
+    } else if texture_file.grayscale {
         process_grayscale_image(&img, texture_file, &mat_yml)
+    } else if texture_file.name == "color" {
+        match &mat_yml.color {
+            Some(color) => process_color_image(img, color),
+            None => img,
+        }
     } else if texture_file.name == "normal" {
-        let normal = mat_yml.normal.as_ref().unwrap_or(&Normal {
-            curve_x: Some(0.0),
-            curve_y: Some(0.0),
-            radius_size_x: Some(0.5),
-            radius_size_y: Some(0.5),
-            noise_angle: Some(0.0),
-            method: 0,
-            strength: Some(1.0),
-        });
-
-        let size = match normal.method {
-            0 => KernelSize::Three,
-            1 => KernelSize::Five,
-            2 => KernelSize::Nine,
-            3 => KernelSize::Low,
-            4 => KernelSize::High,
-            5 => KernelSize::Variance,
-            _ => KernelSize::Three,
-        };
+        let normal = mat_yml.normal.clone().unwrap_or_default();
+        let size = KernelSize::from_method(normal.method);
         if !original_exists {
-            match find_matching_file(path, r".*(?i)height.*\.png$") {
-                Some(file) => generate_normal_map(
-                    &file.path(),
+            let default_size = (img.info.width as u32, img.info.height as u32);
+            match prepare_height(find_height(path, format)?, &mat_yml, default_size) {
+                Some(height) => generate_normal_map(
+                    &height,
                     size,
                     normal.strength.unwrap_or(1.0),
                     normal.curve_x.unwrap_or(0.0),
@@ -165,10 +176,10 @@ fn process_image(
                     &mut img.buf,
                     img.info.width,
                     img.info.height,
-                    normal.curve_x.unwrap(),
-                    normal.curve_y.unwrap(),
-                    normal.radius_size_x.unwrap(),
-                    normal.radius_size_y.unwrap(),
+                    normal.curve_x.unwrap_or(0.0),
+                    normal.curve_y.unwrap_or(0.0),
+                    normal.radius_size_x.unwrap_or(1.0),
+                    normal.radius_size_y.unwrap_or(1.0),
                 ),
                 info: img.info,
                 palette: None,
@@ -178,19 +189,112 @@ fn process_image(
         img
     };
 
-    image_to_base64(&processed_img)
+    let (width, height) = (
+        processed_img.info.width as u32,
+        processed_img.info.height as u32,
+    );
+    // A broken mask only loses the metal tint here, exports still report it
+    let coverage = metal_coverage(path, &mat_yml, width, height).unwrap_or_else(|e| {
+        eprintln!("Previewing {} without its metal: {}", path.display(), e);
+        None
+    });
+    let processed_img = match coverage {
+        Some(coverage) => apply_metal(processed_img, texture_file.name, &coverage),
+        None => processed_img,
+    };
+
+    image_to_base64(&processed_img).map_err(|e| Error::other(path, e))
+}
+
+// The height texture of a material, from its own file or a packed texture
+fn find_height(path: &Path, format: InputFormat) -> CommandResult<Option<DynamicImage>> {
+    match find_source_file(path, r".*(?i)height.*\.png$", format) {
+        Some(file) => Ok(Some(
+            image::open(&file).map_err(|e| Error::parse(&file, e))?,
+        )),
+        None => Ok(decode_packed(path, format).remove("height")),
+    }
+}
+
+// The height map normals are generated from, with the height section applied.
+// A `value` fills a blank `size` map when there's no height texture so edge
+// fade alone can bevel a material. Untouched height maps keep their bit depth.
+pub fn prepare_height(
+    source: Option<DynamicImage>,
+    mat_yml: &MatYml,
+    size: (u32, u32),
+) -> Option<DynamicImage> {
+    let Some(height) = &mat_yml.height else {
+        return source;
+    };
+
+    let img = match source {
+        Some(img) => img.to_luma8(),
+        None if height.value.is_some_and(|value| value > 0.0) => GrayImage::new(size.0, size.1),
+        None => return None,
+    };
+
+    let (width, img_height) = img.dimensions();
+    let buf = process_pixels_height(img.as_raw(), width as usize, img_height as usize, height);
+    GrayImage::from_raw(width, img_height, buf).map(DynamicImage::ImageLuma8)
+}
+
+pub fn process_pixels_height(
+    img: &[u8],
+    width: usize,
+    height: usize,
+    texture: &DefaultsHeight,
+) -> Vec<u8> {
+    let mut buf = process_pixels_grayscale_common(img, width, height, &texture.grayscale());
+
+    let blur_radius = texture.blur_radius.unwrap_or(0.0);
+    if blur_radius > 0.0 {
+        buf = blur_tiled(buf, width as u32, height as u32, blur_radius);
+    }
+
+    let edge_fade = texture.edge_fade.unwrap_or(0.0);
+    if edge_fade > 0.0 {
+        for (i, pixel) in buf.iter_mut().enumerate() {
+            let (x, y) = (i % width, i / width);
+            let edge = x.min(y).min(width - 1 - x).min(height - 1 - y) as f32 + 0.5;
+            *pixel = (*pixel as f32 * (edge / edge_fade).min(1.0)) as u8;
+        }
+    }
+
+    buf
+}
+
+// Blurs a 3x3 tiling of the image and keeps the middle tile, so the edges
+// blur into the opposite side like they do in game
+fn blur_tiled(buf: Vec<u8>, width: u32, height: u32, sigma: f32) -> Vec<u8> {
+    let Some(img) = GrayImage::from_raw(width, height, buf.clone()) else {
+        return buf;
+    };
+
+    let tiled = GrayImage::from_fn(width * 3, height * 3, |x, y| {
+        *img.get_pixel(x % width, y % height)
+    });
+    let blurred = image::imageops::blur(&tiled, sigma);
+
+    image::imageops::crop_imm(&blurred, width, height, width, height)
+        .to_image()
+        .into_raw()
 }
 
 // fn process_normal_map(file_path: &Path) -> Result<PngImage, String> {
 //     Ok((img))
 // }
 
-fn read_png_file(file_path: &Path) -> Result<PngImage, String> {
-    let file = File::open(file_path).map_err(|e| e.to_string())?;
+fn read_png_file(file_path: &Path) -> CommandResult<PngImage> {
+    let file = File::open(file_path).map_err(|e| Error::io(file_path, e))?;
     let decoder = png::Decoder::new(file);
-    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| Error::parse(file_path, e))?;
     let mut buf = vec![0; reader.output_buffer_size()];
-    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| Error::parse(file_path, e))?;
 
     Ok(PngImage {
         buf,
@@ -205,20 +309,32 @@ fn read_png_file(file_path: &Path) -> Result<PngImage, String> {
     })
 }
 
-fn load_mat_yml(path: &Path) -> Result<MatYml, String> {
-    let mat_yml_str = fs::read_to_string(path.join("mat.yml")).map_err(|e| {
-        let err = format!("Failed to read mat.yml file: {}", e);
-        eprintln!("{}", err);
-        err
-    })?;
-
-    let result = serde_yaml::from_str(&mat_yml_str).map_err(|e| {
-        let err = format!("Failed to deserialise mat.yml file: {}", e);
-        eprintln!("{}", err);
-        err
-    });
+// Channels decoded from packed textures only ever come out as 8 bit
+// grayscale, RGB or RGBA
+fn dynamic_to_png(img: image::DynamicImage) -> PngImage {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let (buf, color_type) = match img {
+        image::DynamicImage::ImageLuma8(img) => (img.into_raw(), png::ColorType::Grayscale),
+        image::DynamicImage::ImageRgb8(img) => (img.into_raw(), png::ColorType::Rgb),
+        img => (img.to_rgba8().into_raw(), png::ColorType::Rgba),
+    };
 
-    result
+    PngImage {
+        buf,
+        info: Defaults {
+            bit_depth: png::BitDepth::Eight,
+            color_type,
+            width,
+            height,
+            default_color: None,
+        },
+        palette: None,
+    }
+}
+
+pub fn load_mat_yml(path: &Path) -> CommandResult<MatYml> {
+    let mat_yml_path = path.join("mat.yml");
+    store::read_with(&mat_yml_path, |text| parse_mat_yml(&mat_yml_path, text))
 }
 
 // Create a plain 16x16 image with the filled default colour
@@ -272,11 +388,7 @@ fn process_grayscale_image(
     };
 
     // Textures sharing the same properties, so the processing will be the same
-    if [
-        "opacity", "smooth", "rough", "porosity", "metal", "f0", "sss", "emissive",
-    ]
-    .contains(&texture_file.name)
-    {
+    if GRAYSCALE_SECTIONS.contains(&texture_file.name) {
         if let Some(texture) = get_texture_properties_grayscale(&texture_file.name, mat_yml) {
             let processed =
                 process_pixels_grayscale_common(&luma_img, info.width, info.height, &texture);
@@ -296,12 +408,44 @@ fn process_grayscale_image(
     }
 }
 
-fn process_pixels_grayscale_common(
+// Shows the metal from mat.yml in the hcm and f0 channels the way export
+// writes them, and tints the colour texture with it
+fn apply_metal(mut img: PngImage, texture_name: &str, coverage: &MetalCoverage) -> PngImage {
+    let (width, height) = (img.info.width as u32, img.info.height as u32);
+    let luma = |img: &PngImage| match (img.info.color_type, img.info.bit_depth) {
+        (png::ColorType::Grayscale, png::BitDepth::Eight) => {
+            GrayImage::from_raw(width, height, img.buf.clone())
+        }
+        _ => None,
+    };
+
+    let gray = match texture_name {
+        "hcm" => Some(coverage.apply_hcm(luma(&img), width, height)),
+        "f0" => coverage.apply_f0(luma(&img)),
+        "color" => {
+            match (img.info.color_type, img.info.bit_depth) {
+                (png::ColorType::Rgb, png::BitDepth::Eight) => coverage.tint(&mut img.buf, 3),
+                (png::ColorType::Rgba, png::BitDepth::Eight) => coverage.tint(&mut img.buf, 4),
+                _ => (),
+            }
+            None
+        }
+        _ => None,
+    };
+
+    match gray {
+        Some(gray) => dynamic_to_png(DynamicImage::ImageLuma8(gray)),
+        None => img,
+    }
+}
+
+pub fn process_pixels_grayscale_common(
     img: &[u8],
     width: usize,
     height: usize,
     texture: &DefaultsGrayscale,
 ) -> Vec<u8> {
+    let invert = texture.invert.unwrap_or(false);
     let value = texture.value.unwrap_or(0.0);
     let shift = texture.shift.unwrap_or(0.0);
     let scale = texture.scale.unwrap_or(1.0);
@@ -319,6 +463,7 @@ fn process_pixels_grayscale_common(
                         // No need to modify the pixels if there is a value present since it overwrites the image
                         value.clamp(0.0, 255.0) as u8
                     } else {
+                        let pixel = if invert { 255 - pixel } else { pixel };
                         let current_value = pixel as f32 / 255.0;
                         let new_value = ((current_value + shift) * scale).clamp(0.0, 1.0);
                         (new_value * 255.0) as u8
@@ -331,24 +476,97 @@ fn process_pixels_grayscale_common(
     processed
 }
 
-fn find_matching_file(path: &Path, pattern: &str) -> Option<fs::DirEntry> {
-    // Filter all the files/paths in the specified dir and returns false or the path to file
-    fs::read_dir(path)
-        .ok()?
-        .filter_map(Result::ok)
-        .find(|entry| {
-            entry.file_name().to_str().map_or(false, |file_name| {
-                regex::Regex::new(pattern).ok().unwrap().is_match(file_name)
-            })
+fn process_color_image(img: PngImage, color: &DefaultsColor) -> PngImage {
+    let channels = match (img.info.color_type, img.info.bit_depth) {
+        (png::ColorType::Rgb, png::BitDepth::Eight) => 3,
+        (png::ColorType::Rgba, png::BitDepth::Eight) => 4,
+        _ => return img,
+    };
+
+    PngImage {
+        buf: process_pixels_color(&img.buf, channels, color),
+        info: img.info,
+        palette: None,
+    }
+}
+
+// Applies the colour section to 8 bit RGB (`channels` 3) or RGBA (4) pixels,
+// alpha is left alone
+pub fn process_pixels_color(img: &[u8], channels: usize, color: &DefaultsColor) -> Vec<u8> {
+    let tint = color
+        .tint
+        .as_deref()
+        .and_then(DefaultsColor::parse_tint)
+        .map_or([1.0; 3], |tint| tint.map(|c| c as f32 / 255.0));
+    let hue_shift = color.hue_shift.unwrap_or(0.0);
+    let saturation = color.saturation.unwrap_or(1.0).max(0.0);
+    let contrast = color.contrast.unwrap_or(1.0).max(0.0);
+    let brightness = color.brightness.unwrap_or(0.0);
+    let overrides = color.overrides();
+
+    img.par_chunks(channels)
+        .flat_map_iter(|pixel| {
+            let mut rgb = [0, 1, 2].map(|i| pixel[i] as f32 / 255.0 * tint[i]);
+
+            if hue_shift != 0.0 || saturation != 1.0 {
+                let (h, s, v) = rgb_to_hsv(rgb);
+                rgb = hsv_to_rgb(h + hue_shift, (s * saturation).clamp(0.0, 1.0), v);
+            }
+
+            let mut out = pixel.to_vec();
+            for i in 0..3 {
+                let value = (rgb[i] - 0.5) * contrast + 0.5 + brightness;
+                out[i] = overrides[i].unwrap_or((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+            out
         })
+        .collect()
+}
+
+// Hue in degrees, saturation and value from 0 to 1
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+
+    (h, s, max)
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let c = v * s;
+    let x = c * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+
+    [r + m, g + m, b + m]
 }
 
-fn get_texture_properties_grayscale(
+pub fn get_texture_properties_grayscale(
     texture_name: &str,
     mat_yml: &MatYml,
 ) -> Option<DefaultsGrayscale> {
     let result = match texture_name {
         "opacity" => mat_yml.opacity.clone(),
+        "occlusion" => mat_yml.occlusion.clone(),
         "rough" => mat_yml.rough.clone(),
         "smooth" => mat_yml.smooth.clone(),
         "metal" => mat_yml.metal.clone(),
@@ -362,6 +580,83 @@ fn get_texture_properties_grayscale(
     result
 }
 
+// Grayscale textures whose defaults can be edited from the interface
+const GRAYSCALE_SECTIONS: [&str; 9] = [
+    "opacity",
+    "occlusion",
+    "rough",
+    "smooth",
+    "metal",
+    "f0",
+    "porosity",
+    "sss",
+    "emissive",
+];
+
+fn yaml_scalar(value: impl serde::Serialize) -> String {
+    serde_yaml::to_string(&value)
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn patch_error(path: &Path, error: PatchError) -> Error {
+    match error {
+        PatchError::Yaml(e) => Error::parse(path, e),
+        PatchError::NotASection { key, found } => {
+            Error::invalid_field(path, key, format!("Must be a section, found {}", found))
+        }
+        e @ PatchError::WouldDropComments { .. } => Error::conflict(path, e),
+    }
+}
+
+// Sets only the given fields of one section, everything else in mat.yml is
+// left as the user wrote it. Fields that would only repeat the value inherited
+// from a template are left unset so they keep following it.
+fn patch_mat_yml(path: &Path, section: &str, fields: &[(&str, String)]) -> CommandResult<()> {
+    let mat_yml_path = path.join("mat.yml");
+    let _lock = FileLock::acquire(&mat_yml_path)?;
+
+    // Also puts back the backup when mat.yml can't be read
+    load_mat_yml(path)?;
+    let mut mat_yml_str =
+        fs::read_to_string(&mat_yml_path).map_err(|e| Error::io(&mat_yml_path, e))?;
+    let local = migrate_mat_yml(&mat_yml_path, &mat_yml_str)?;
+    let inherited = template::inherited(&mat_yml_path, &local)?;
+
+    // Version 1 only added the field, so setting it is the whole upgrade
+    let version = MAT_YML_VERSION.to_string();
+    mat_yml_str = yaml_patch::set_leading_value(&mat_yml_str, "schema_version", &version)
+        .map_err(|e| patch_error(&mat_yml_path, e))?;
+
+    for (key, value) in fields {
+        let parsed: serde_yaml::Value =
+            serde_yaml::from_str(value).map_err(|e| Error::parse(&mat_yml_path, e))?;
+        if template::is_inherited_value(&inherited, &local, section, key, &parsed) {
+            continue;
+        }
+
+        mat_yml_str = yaml_patch::set_value(&mat_yml_str, &[section, key], value)
+            .map_err(|e| patch_error(&mat_yml_path, e))?;
+    }
+    parse_mat_yml(&mat_yml_path, &mat_yml_str)?;
+
+    store::write_atomic(&mat_yml_path, mat_yml_str.as_bytes())
+}
+
+// Which fields of a mat.yml section come from a template, mapped to the
+// template they're read from. Fields the material sets itself are left out.
+#[tauri::command]
+pub fn get_inherited_fields(
+    material_path: String,
+    section: String,
+) -> CommandResult<BTreeMap<String, String>> {
+    let mat_yml_path = Path::new(&material_path).join("mat.yml");
+    let text = fs::read_to_string(&mat_yml_path).map_err(|e| Error::io(&mat_yml_path, e))?;
+    let local = migrate_mat_yml(&mat_yml_path, &text)?;
+
+    template::inherited_fields(&mat_yml_path, &local, &section)
+}
+
 #[tauri::command]
 pub fn update_defaults_grayscale(
     material_path: String,
@@ -369,52 +664,148 @@ pub fn update_defaults_grayscale(
     value: String,
     shift: String,
     scale: String,
-) -> Result<bool, String> {
+    invert: Option<String>,
+) -> CommandResult<bool> {
+    let path = Path::new(&material_path);
+    if !path.exists() {
+        return Err(Error::not_found(path, "Selected path does not exist"));
+    }
+    if !GRAYSCALE_SECTIONS.contains(&texture.as_str()) {
+        return Err(Error::invalid_input(
+            &texture,
+            "Texture has no grayscale defaults",
+        ));
+    }
+
+    let parsed_value = value.parse::<f32>().unwrap_or(0.0).clamp(0.0, 255.0);
+    let parsed_shift = shift.parse::<f32>().unwrap_or(0.0);
+    let parsed_scale = scale.parse::<f32>().unwrap_or(1.0);
+
+    let mut fields = vec![
+        ("value", yaml_scalar(parsed_value)),
+        ("shift", yaml_scalar(parsed_shift)),
+        ("scale", yaml_scalar(parsed_scale)),
+    ];
+    // Older callers don't send it, which leaves the file's own setting alone
+    if let Some(invert) = invert {
+        fields.push((
+            "invert",
+            invert.parse::<bool>().unwrap_or(false).to_string(),
+        ));
+    }
+    patch_mat_yml(path, &texture, &fields)?;
+
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn update_hcm(
+    material_path: String,
+    metal: String,
+    mask: Option<String>,
+) -> CommandResult<bool> {
     let path = Path::new(&material_path);
     if !path.exists() {
-        return Err(String::from("Selected path does not exist."));
+        return Err(Error::not_found(path, "Selected path does not exist"));
     }
 
-    let mat_yml_str = fs::read_to_string(path.join("mat.yml")).map_err(|e| {
-        let err = format!("Failed to read mat.yml file {}\n {}", material_path, e);
-        eprintln!("{}", err);
-        err
-    })?;
-    let mut mat_yml: MatYml = serde_yaml::from_str(&mat_yml_str).map_err(|e| {
-        let err = format!("Failed to deserialise mat.yml file {}", e);
-        eprintln!("{}", err);
-        err
-    })?;
+    let parsed_metal: Hcm = serde_yaml::from_str(&metal)
+        .map_err(|_| Error::invalid_input(&metal, "Unknown hardcoded metal"))?;
+
+    let mut fields = vec![("metal", yaml_scalar(parsed_metal))];
+    if let Some(mask) = mask {
+        fields.push(("mask", yaml_scalar(mask.trim())));
+    }
+    patch_mat_yml(path, "hcm", &fields)?;
+
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn update_height(
+    material_path: String,
+    value: String,
+    shift: String,
+    scale: String,
+    invert: String,
+    blur_radius: String,
+    edge_fade: String,
+) -> CommandResult<bool> {
+    let path = Path::new(&material_path);
+    if !path.exists() {
+        return Err(Error::not_found(path, "Selected path does not exist"));
+    }
 
     let parsed_value = value.parse::<f32>().unwrap_or(0.0).clamp(0.0, 255.0);
     let parsed_shift = shift.parse::<f32>().unwrap_or(0.0);
     let parsed_scale = scale.parse::<f32>().unwrap_or(1.0);
+    let parsed_invert = invert.parse::<bool>().unwrap_or(false);
+    let parsed_blur_radius = blur_radius.parse::<f32>().unwrap_or(0.0).max(0.0);
+    let parsed_edge_fade = edge_fade.parse::<f32>().unwrap_or(0.0).max(0.0);
 
-    let new_defaults = DefaultsGrayscale {
-        value: Some(parsed_value),
-        shift: Some(parsed_shift),
-        scale: Some(parsed_scale),
-    };
+    patch_mat_yml(
+        path,
+        "height",
+        &[
+            ("invert", parsed_invert.to_string()),
+            ("value", yaml_scalar(parsed_value)),
+            ("shift", yaml_scalar(parsed_shift)),
+            ("scale", yaml_scalar(parsed_scale)),
+            ("blur_radius", yaml_scalar(parsed_blur_radius)),
+            ("edge_fade", yaml_scalar(parsed_edge_fade)),
+        ],
+    )?;
 
-    match texture.as_str() {
-        "opacity" => mat_yml.opacity = Some(new_defaults),
-        "rough" => mat_yml.rough = Some(new_defaults),
-        "smooth" => mat_yml.smooth = Some(new_defaults),
-        "metal" => mat_yml.metal = Some(new_defaults),
-        "f0" => mat_yml.f0 = Some(new_defaults),
-        "porosity" => mat_yml.porosity = Some(new_defaults),
-        "sss" => mat_yml.sss = Some(new_defaults),
-        "emissive" => mat_yml.emissive = Some(new_defaults),
-        _ => (),
+    Ok(true)
+}
+
+// One argument per field, like the other update commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_color(
+    material_path: String,
+    tint: String,
+    hue_shift: String,
+    saturation: String,
+    contrast: String,
+    brightness: String,
+    red: String,
+    green: String,
+    blue: String,
+) -> CommandResult<bool> {
+    let path = Path::new(&material_path);
+    if !path.exists() {
+        return Err(Error::not_found(path, "Selected path does not exist"));
     }
 
-    let updated_mat_yml = serde_yaml::to_string(&mat_yml).map_err(|e| {
-        let err = format!("Failed to serialize mat.yml file {}", e);
-        eprintln!("{}", err);
-        err
-    })?;
+    let parsed_tint = DefaultsColor::parse_tint(tint.trim()).unwrap_or([255, 255, 255]);
+    let parsed_hue_shift = hue_shift.parse::<f32>().unwrap_or(0.0);
+    let parsed_saturation = saturation.parse::<f32>().unwrap_or(1.0).max(0.0);
+    let parsed_contrast = contrast.parse::<f32>().unwrap_or(1.0).max(0.0);
+    let parsed_brightness = brightness.parse::<f32>().unwrap_or(0.0).clamp(-1.0, 1.0);
+    let [parsed_red, parsed_green, parsed_blue] =
+        [red, green, blue].map(|value| value.parse::<f32>().unwrap_or(0.0).clamp(0.0, 255.0));
 
-    fs::write(&path.join("mat.yml"), updated_mat_yml).map_err(|e| e.to_string())?;
+    patch_mat_yml(
+        path,
+        "color",
+        &[
+            (
+                "tint",
+                format!(
+                    "'#{:02x}{:02x}{:02x}'",
+                    parsed_tint[0], parsed_tint[1], parsed_tint[2]
+                ),
+            ),
+            ("hue_shift", yaml_scalar(parsed_hue_shift)),
+            ("saturation", yaml_scalar(parsed_saturation)),
+            ("contrast", yaml_scalar(parsed_contrast)),
+            ("brightness", yaml_scalar(parsed_brightness)),
+            ("red", yaml_scalar(parsed_red)),
+            ("green", yaml_scalar(parsed_green)),
+            ("blue", yaml_scalar(parsed_blue)),
+        ],
+    )?;
 
     Ok(true)
 }
@@ -451,23 +842,12 @@ pub fn update_normals(
     noise_angle: String,
     method: String,
     strength: String,
-) -> Result<bool, String> {
+) -> CommandResult<bool> {
     let path = Path::new(&material_path);
     if !path.exists() {
-        return Err(String::from("Selected path does not exist."));
+        return Err(Error::not_found(path, "Selected path does not exist"));
     }
 
-    let mat_yml_str = fs::read_to_string(path.join("mat.yml")).map_err(|e| {
-        let err = format!("Failed to read mat.yml file {}\n {}", material_path, e);
-        eprintln!("{}", err);
-        err
-    })?;
-    let mut mat_yml: MatYml = serde_yaml::from_str(&mat_yml_str).map_err(|e| {
-        let err = format!("Failed to deserialise mat.yml file {}", e);
-        eprintln!("{}", err);
-        err
-    })?;
-
     let parsed_curve_x = curve_x.parse::<f32>().unwrap_or(0.0);
     let parsed_curve_y = curve_y.parse::<f32>().unwrap_or(0.0);
     let parsed_radius_size_x = radius_size_x.parse::<f32>().unwrap_or(0.5);
@@ -477,23 +857,214 @@ pub fn update_normals(
     let parsed_method = method.parse::<usize>().unwrap_or(0);
     let parsed_strength = strength.parse::<f32>().unwrap_or(1.0);
 
-    mat_yml.normal = Some(Normal {
-        curve_x: Some(parsed_curve_x),
-        curve_y: Some(parsed_curve_y),
-        radius_size_x: Some(parsed_radius_size_x),
-        radius_size_y: Some(parsed_radius_size_y),
-        noise_angle: Some(parsed_noise_angle),
-        method: parsed_method,
-        strength: Some(parsed_strength),
-    });
+    patch_mat_yml(
+        path,
+        "normal",
+        &[
+            ("curve_x", yaml_scalar(parsed_curve_x)),
+            ("curve_y", yaml_scalar(parsed_curve_y)),
+            ("radius_size_x", yaml_scalar(parsed_radius_size_x)),
+            ("radius_size_y", yaml_scalar(parsed_radius_size_y)),
+            ("noise_angle", yaml_scalar(parsed_noise_angle)),
+            ("method", yaml_scalar(parsed_method)),
+            ("strength", yaml_scalar(parsed_strength)),
+        ],
+    )?;
 
-    let updated_mat_yml = serde_yaml::to_string(&mat_yml).map_err(|e| {
-        let err = format!("Failed to serialize mat.yml file {}", e);
-        eprintln!("{}", err);
-        err
-    })?;
+    Ok(true)
+}
 
-    fs::write(&path.join("mat.yml"), updated_mat_yml).map_err(|e| e.to_string())?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(true)
+    fn material(mat_yml: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("mat.yml"), mat_yml).unwrap();
+        dir
+    }
+
+    fn read(dir: &tempfile::TempDir) -> String {
+        fs::read_to_string(dir.path().join("mat.yml")).unwrap()
+    }
+
+    #[test]
+    fn patch_adds_schema_version_after_document_marker() {
+        let dir = material("# Stone\n---\nsmooth:\n  value: 10\n");
+        patch_mat_yml(dir.path(), "smooth", &[("scale", "2".to_string())]).unwrap();
+
+        assert_eq!(
+            read(&dir),
+            "# Stone\n---\nschema_version: 1\nsmooth:\n  value: 10\n  scale: 2\n"
+        );
+        assert_eq!(
+            load_mat_yml(dir.path()).unwrap().schema_version,
+            MAT_YML_VERSION
+        );
+    }
+
+    #[test]
+    fn broken_mat_yml_is_not_restored_from_backup() {
+        let dir = material("smooth:\n  value: 10\n");
+        patch_mat_yml(dir.path(), "smooth", &[("value", "20".to_string())]).unwrap();
+        fs::write(dir.path().join("mat.yml"), "smooth: [broken\n").unwrap();
+
+        let Err(err) = load_mat_yml(dir.path()) else {
+            panic!("broken mat.yml loaded");
+        };
+        assert!(matches!(err, Error::Parse { .. }), "{err}");
+        assert!(err.to_string().contains("mat.yml"), "{err}");
+        assert_eq!(read(&dir), "smooth: [broken\n");
+    }
+
+    #[test]
+    fn patch_refuses_to_drop_comments() {
+        let doc = "schema_version: 1\n# Polished\nsmooth: {value: 10}\n";
+        let dir = material(doc);
+
+        let Err(err) = patch_mat_yml(dir.path(), "smooth", &[("value", "20".to_string())]) else {
+            panic!("comments were dropped");
+        };
+        assert!(matches!(err, Error::Conflict { .. }), "{err}");
+        assert_eq!(read(&dir), doc);
+    }
+
+    #[test]
+    fn broken_metal_mask_only_fails_exports() {
+        let dir = material("hcm:\n  metal: gold\n  mask: missing.png\n");
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([200, 200, 200, 255]))
+            .save(dir.path().join("color.png"))
+            .unwrap();
+        let mat_yml = Arc::new(load_mat_yml(dir.path()).unwrap());
+        let color = TEXTURE_FILES.iter().find(|t| t.name == "color").unwrap();
+
+        assert!(process_image(dir.path(), color, mat_yml, InputFormat::Raw).is_ok());
+        let export = crate::core::export::load_material(dir.path(), InputFormat::Raw);
+        assert!(export
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("Metal mask does not exist"));
+    }
+
+    #[test]
+    fn patch_leaves_inherited_values_unset() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("_templates")).unwrap();
+        fs::write(
+            dir.path().join("_templates/ore.yml"),
+            "smooth:\n  value: 20\n",
+        )
+        .unwrap();
+        let material = dir.path().join("stone");
+        fs::create_dir_all(&material).unwrap();
+        fs::write(
+            material.join("mat.yml"),
+            "schema_version: 1\nextends: ../_templates/ore.yml\n",
+        )
+        .unwrap();
+        let mat_yml = || fs::read_to_string(material.join("mat.yml")).unwrap();
+
+        let fields = [("value", "20.0".to_string()), ("scale", "2".to_string())];
+        patch_mat_yml(&material, "smooth", &fields).unwrap();
+        assert_eq!(
+            mat_yml(),
+            "schema_version: 1\nextends: ../_templates/ore.yml\nsmooth:\n  scale: 2\n"
+        );
+
+        patch_mat_yml(&material, "smooth", &[("value", "25".to_string())]).unwrap();
+        assert!(mat_yml().ends_with("smooth:\n  scale: 2\n  value: 25\n"));
+        let smooth = load_mat_yml(&material).unwrap().smooth.unwrap();
+        assert_eq!((smooth.value, smooth.scale), (Some(25.0), Some(2.0)));
+    }
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    // Every 8 bit RGBA pixel with channels in steps of 15, plus varied alpha
+    fn test_pixels() -> Vec<u8> {
+        let steps = (0..=255u8).step_by(15);
+        steps
+            .clone()
+            .flat_map(|r| steps.clone().map(move |g| (r, g)))
+            .flat_map(|(r, g)| steps.clone().map(move |b| [r, g, b, r ^ b]))
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn hsv_round_trip() {
+        for pixel in test_pixels().chunks(4) {
+            let rgb = [0, 1, 2].map(|i| pixel[i] as f32 / 255.0);
+            let (h, s, v) = rgb_to_hsv(rgb);
+            assert!((0.0..360.0).contains(&h));
+            assert_close(hsv_to_rgb(h, s, v), rgb);
+        }
+    }
+
+    #[test]
+    fn hue_wraps_at_360() {
+        assert_close(hsv_to_rgb(0.0, 1.0, 1.0), [1.0, 0.0, 0.0]);
+        assert_close(hsv_to_rgb(360.0, 1.0, 1.0), [1.0, 0.0, 0.0]);
+        assert_close(hsv_to_rgb(480.0, 1.0, 1.0), hsv_to_rgb(120.0, 1.0, 1.0));
+        assert_close(hsv_to_rgb(-60.0, 1.0, 1.0), hsv_to_rgb(300.0, 1.0, 1.0));
+
+        let red = [255, 0, 0, 255];
+        let shifted = |hue_shift| {
+            let color = DefaultsColor {
+                hue_shift: Some(hue_shift),
+                ..Default::default()
+            };
+            process_pixels_color(&red, 4, &color)
+        };
+        assert_eq!(shifted(360.0), red);
+        assert_eq!(shifted(120.0), [0, 255, 0, 255]);
+        assert_eq!(shifted(-120.0), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn identity_color_keeps_pixels() {
+        let pixels = test_pixels();
+        let identity = DefaultsColor {
+            tint: Some("#ffffff".to_string()),
+            hue_shift: Some(0.0),
+            saturation: Some(1.0),
+            contrast: Some(1.0),
+            brightness: Some(0.0),
+            red: Some(0.0),
+            green: Some(0.0),
+            blue: Some(0.0),
+        };
+
+        for color in [DefaultsColor::default(), identity] {
+            assert_eq!(process_pixels_color(&pixels, 4, &color), pixels);
+        }
+
+        let rgb: Vec<u8> = pixels
+            .chunks(4)
+            .flat_map(|pixel| pixel[..3].to_vec())
+            .collect();
+        assert_eq!(
+            process_pixels_color(&rgb, 3, &DefaultsColor::default()),
+            rgb
+        );
+    }
+
+    #[test]
+    fn channel_overrides_keep_alpha() {
+        let pixels = test_pixels();
+        let color = DefaultsColor {
+            red: Some(200.0),
+            blue: Some(10.0),
+            ..Default::default()
+        };
+
+        let processed = process_pixels_color(&pixels, 4, &color);
+        for (before, after) in pixels.chunks(4).zip(processed.chunks(4)) {
+            assert_eq!(after, [200, before[1], 10, before[3]]);
+        }
+    }
 }
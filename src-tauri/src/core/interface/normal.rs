@@ -1,5 +1,3 @@
-use std::path::Path;
-
 use super::structs::PngImage;
 use crate::core::{
     interface::structs::Defaults,
@@ -15,7 +13,7 @@ use nalgebra::{Rotation3, Vector3};
 // ];
 
 pub fn generate_normal_map(
-    path: &Path,
+    img: &DynamicImage,
     size: KernelSize,
     strength: f32,
     curve_x: f32,
@@ -23,7 +21,6 @@ pub fn generate_normal_map(
     radius_x: f32,
     radius_y: f32,
 ) -> PngImage {
-    let img = image::open(path).unwrap();
     let (width, height) = img.dimensions();
 
     // Create a 3x3 tiled image
@@ -1,42 +1,243 @@
 use std::borrow::Cow;
 
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
+use crate::core::{codec::labpbr::HCM_MIN, migration::MAT_YML_VERSION};
+
+// The `///` comments on the mat.yml and project.yml structs end up as
+// descriptions in the generated JSON Schema
+/// Settings for one material folder
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Default)]
 pub struct MatYml {
-    // pub color: Option<DefaultsGrayscale>,
+    /// Version of the mat.yml layout, older files are upgraded when loaded
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Template to inherit settings from, keys set here win. Either a path
+    /// relative to this file or the name of a file in the project's
+    /// `_templates` folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Colour adjustments applied to the colour texture
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<DefaultsColor>,
+    /// Height map adjustments, also used when normals are generated from it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<DefaultsHeight>,
+    /// How the normal map is generated from height and bent
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub normal: Option<Normal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub opacity: Option<DefaultsGrayscale>,
+    /// Ambient occlusion, 255 is unoccluded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occlusion: Option<DefaultsGrayscale>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub smooth: Option<DefaultsGrayscale>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rough: Option<DefaultsGrayscale>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub porosity: Option<DefaultsGrayscale>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub metal: Option<DefaultsGrayscale>,
+    /// Hardcoded metal used for the whole material, or only where its `mask`
+    /// texture is white
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hcm: Option<DefaultsHcm>,
+    /// Reflectance, values from 230 up select a hardcoded metal
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub f0: Option<DefaultsGrayscale>,
+    /// Subsurface scattering
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sss: Option<DefaultsGrayscale>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub emissive: Option<DefaultsGrayscale>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
+impl MatYml {
+    // An empty mat.yml at the current schema version
+    pub fn new() -> Self {
+        MatYml {
+            schema_version: MAT_YML_VERSION,
+            ..Default::default()
+        }
+    }
+}
+
+/// Overrides for a grayscale channel
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Default)]
 pub struct DefaultsGrayscale {
+    /// Flips the texture before the shift and scale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invert: Option<bool>,
+    /// Fills the whole channel with this value, 0 keeps the texture
+    #[schemars(range(min = 0, max = 255))]
+    pub value: Option<f32>,
+    /// Multiplies every pixel after the shift, 1 keeps the texture
+    pub scale: Option<f32>,
+    /// Added to every pixel (0 to 1 range) before scaling
+    pub shift: Option<f32>,
+}
+
+/// Overrides for the colour texture, applied in the order listed
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Default)]
+pub struct DefaultsColor {
+    /// Multiplies every pixel by this colour, as `#rrggbb`
+    #[schemars(regex(pattern = r"^#?[0-9a-fA-F]{6}$"))]
+    pub tint: Option<String>,
+    /// Rotates the hue, in degrees
+    pub hue_shift: Option<f32>,
+    /// Multiplies the saturation, 0 is grayscale and 1 keeps the texture
+    #[schemars(range(min = 0))]
+    pub saturation: Option<f32>,
+    /// Spreads the values away from mid gray, 1 keeps the texture
+    #[schemars(range(min = 0))]
+    pub contrast: Option<f32>,
+    /// Added to every channel (-1 to 1 range)
+    #[schemars(range(min = -1, max = 1))]
+    pub brightness: Option<f32>,
+    /// Fills the red channel with this value, 0 keeps the texture
+    #[schemars(range(min = 0, max = 255))]
+    pub red: Option<f32>,
+    /// Fills the green channel with this value, 0 keeps the texture
+    #[schemars(range(min = 0, max = 255))]
+    pub green: Option<f32>,
+    /// Fills the blue channel with this value, 0 keeps the texture
+    #[schemars(range(min = 0, max = 255))]
+    pub blue: Option<f32>,
+}
+
+impl DefaultsColor {
+    // `#rrggbb` or `rrggbb` as RGB
+    pub fn parse_tint(tint: &str) -> Option<[u8; 3]> {
+        let hex = tint.strip_prefix('#').unwrap_or(tint);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some([channel(0)?, channel(2)?, channel(4)?])
+    }
+
+    // The per-channel value overrides, 0 keeps the texture like the grayscale
+    // `value`
+    pub fn overrides(&self) -> [Option<u8>; 3] {
+        [self.red, self.green, self.blue].map(|value| {
+            value
+                .filter(|&v| v > 0.0)
+                .map(|v| v.clamp(0.0, 255.0) as u8)
+        })
+    }
+}
+
+/// Overrides for the height map, applied in the order listed
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Default)]
+pub struct DefaultsHeight {
+    /// Flips the height map, for textures where white is deepest
+    pub invert: Option<bool>,
+    /// Fills the whole channel with this value, 0 keeps the texture
+    #[schemars(range(min = 0, max = 255))]
     pub value: Option<f32>,
+    /// Multiplies every pixel after the shift, 1 keeps the texture
     pub scale: Option<f32>,
+    /// Added to every pixel (0 to 1 range) before scaling
     pub shift: Option<f32>,
+    /// Gaussian blur, in pixels. The texture wraps so tiling stays seamless
+    #[schemars(range(min = 0))]
+    pub blur_radius: Option<f32>,
+    /// Lowers the height to 0 towards the texture's edges over this many
+    /// pixels
+    #[schemars(range(min = 0))]
+    pub edge_fade: Option<f32>,
+}
+
+impl DefaultsHeight {
+    pub fn grayscale(&self) -> DefaultsGrayscale {
+        DefaultsGrayscale {
+            invert: self.invert,
+            value: self.value,
+            scale: self.scale,
+            shift: self.shift,
+        }
+    }
+}
+
+/// The LabPBR hardcoded metals, or the material's own f0
+#[derive(
+    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Hcm {
+    Iron,
+    Gold,
+    Aluminum,
+    Chrome,
+    Copper,
+    Lead,
+    Platinum,
+    Silver,
+    /// No hardcoded metal, reflectance comes from the f0 texture
+    CustomF0,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+impl Hcm {
+    // The value written into hcm.png, 230 to 237 for the hardcoded metals and
+    // 0 for custom f0
+    pub fn index(self) -> u8 {
+        match self {
+            Hcm::CustomF0 => 0,
+            metal => HCM_MIN + metal as u8,
+        }
+    }
+}
+
+/// Hardcoded metal settings
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Default)]
+pub struct DefaultsHcm {
+    /// Written over hcm.png, for the whole material or where `mask` is white
+    pub metal: Option<Hcm>,
+    /// Texture in the material folder limiting the metal to its white parts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<String>,
+}
+
+/// Normal map generation and filtering
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug)]
 pub struct Normal {
     // Filtering
+    /// Bends the normals across the texture's height, in degrees
     pub curve_x: Option<f32>,
+    /// Bends the normals across the texture's width, in degrees
     pub curve_y: Option<f32>,
 
+    /// How much of the texture the vertical bend covers
     pub radius_size_x: Option<f32>,
+    /// How much of the texture the horizontal bend covers
     pub radius_size_y: Option<f32>,
 
+    /// Random tilt added to every normal, in degrees
     pub noise_angle: Option<f32>,
 
     // Generate from height
+    /// Kernel used to generate normals from height: 0 3x3, 1 5x5, 2 9x9,
+    /// 3 low, 4 high, 5 variance
+    #[schemars(range(min = 0, max = 5))]
     pub method: usize,
+    /// Height difference multiplier when generating normals
     pub strength: Option<f32>,
 }
 
+impl Default for Normal {
+    fn default() -> Self {
+        Normal {
+            curve_x: Some(0.0),
+            curve_y: Some(0.0),
+            radius_size_x: Some(0.5),
+            radius_size_y: Some(0.5),
+            noise_angle: Some(0.0),
+            method: 0,
+            strength: Some(1.0),
+        }
+    }
+}
+
 pub struct TextureFile {
     pub name: &'static str,
     pub pattern: &'static str,
@@ -0,0 +1,302 @@
+// The LabPBR hardcoded metals. The f0 channel's 230 to 237 pick one of these
+// presets instead of storing a reflectance, shaders then use the preset's
+// complex index of refraction (n, k) per RGB channel.
+//
+// mat.yml's `hcm` section assigns a metal to the whole material or to the
+// white parts of a mask texture. Export writes it into hcm.png's channel and
+// clears f0 under it, the preview tints the colour texture with the metal.
+
+use image::{imageops::FilterType, GrayImage};
+use std::path::Path;
+
+use super::{
+    error::{CommandResult, Error},
+    interface::structs::{Hcm, MatYml},
+};
+
+pub struct MetalPreset {
+    pub metal: Hcm,
+    pub name: &'static str,
+    // Index of refraction and extinction coefficient for red, green and blue
+    pub n: [f32; 3],
+    pub k: [f32; 3],
+}
+
+// Values from the LabPBR material standard
+pub const METAL_PRESETS: [MetalPreset; 8] = [
+    MetalPreset {
+        metal: Hcm::Iron,
+        name: "Iron",
+        n: [2.9114, 2.9497, 2.5845],
+        k: [3.0893, 2.9318, 2.7670],
+    },
+    MetalPreset {
+        metal: Hcm::Gold,
+        name: "Gold",
+        n: [0.18299, 0.42108, 1.3734],
+        k: [3.4242, 2.3459, 1.7704],
+    },
+    MetalPreset {
+        metal: Hcm::Aluminum,
+        name: "Aluminum",
+        n: [1.3456, 0.96521, 0.61722],
+        k: [7.4746, 6.3995, 5.3031],
+    },
+    MetalPreset {
+        metal: Hcm::Chrome,
+        name: "Chrome",
+        n: [3.1071, 3.1812, 2.3230],
+        k: [3.3314, 3.3291, 3.1350],
+    },
+    MetalPreset {
+        metal: Hcm::Copper,
+        name: "Copper",
+        n: [0.27105, 0.67693, 1.3164],
+        k: [3.6092, 2.6248, 2.2921],
+    },
+    MetalPreset {
+        metal: Hcm::Lead,
+        name: "Lead",
+        n: [1.9100, 1.8300, 1.4400],
+        k: [3.5100, 3.4000, 3.1800],
+    },
+    MetalPreset {
+        metal: Hcm::Platinum,
+        name: "Platinum",
+        n: [2.3757, 2.0847, 1.8453],
+        k: [4.2655, 3.7153, 3.1365],
+    },
+    MetalPreset {
+        metal: Hcm::Silver,
+        name: "Silver",
+        n: [0.15943, 0.14512, 0.13547],
+        k: [3.9291, 3.1900, 2.3808],
+    },
+];
+
+impl MetalPreset {
+    // Linear reflectance at normal incidence, from the Fresnel equations for
+    // a conductor in air
+    pub fn f0(&self) -> [f32; 3] {
+        [0, 1, 2].map(|i| {
+            let (n, k) = (self.n[i], self.k[i]);
+            ((n - 1.0).powi(2) + k * k) / ((n + 1.0).powi(2) + k * k)
+        })
+    }
+
+    // The reflectance as an sRGB colour, which is what the metal looks like
+    pub fn color(&self) -> [u8; 3] {
+        self.f0().map(|c| {
+            let srgb = if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (srgb.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+    }
+}
+
+pub fn preset(metal: Hcm) -> Option<&'static MetalPreset> {
+    METAL_PRESETS.iter().find(|preset| preset.metal == metal)
+}
+
+// Where the metal from mat.yml applies, at the material's size
+pub struct MetalCoverage {
+    pub metal: Hcm,
+    covered: Vec<bool>,
+}
+
+impl MetalCoverage {
+    fn covers(&self, i: usize) -> bool {
+        self.covered.get(i).copied().unwrap_or(false)
+    }
+
+    // hcm.png with the metal's index written over the covered pixels
+    pub fn apply_hcm(&self, hcm: Option<GrayImage>, width: u32, height: u32) -> GrayImage {
+        let mut hcm = hcm.unwrap_or_else(|| GrayImage::new(width, height));
+        for (i, pixel) in hcm.pixels_mut().enumerate() {
+            if self.covers(i) {
+                pixel[0] = self.metal.index();
+            }
+        }
+        hcm
+    }
+
+    // f0.png is 0 wherever a hardcoded metal is used. Custom f0 keeps it, as
+    // that's where its reflectance comes from.
+    pub fn apply_f0(&self, f0: Option<GrayImage>) -> Option<GrayImage> {
+        if self.metal == Hcm::CustomF0 {
+            return f0;
+        }
+
+        f0.map(|mut f0| {
+            for (i, pixel) in f0.pixels_mut().enumerate() {
+                if self.covers(i) {
+                    pixel[0] = 0;
+                }
+            }
+            f0
+        })
+    }
+
+    // Multiplies the covered pixels of 8 bit RGB or RGBA by the metal's colour
+    pub fn tint(&self, buf: &mut [u8], channels: usize) {
+        let Some(preset) = preset(self.metal) else {
+            return;
+        };
+        let color = preset.color();
+
+        for (i, pixel) in buf.chunks_mut(channels).enumerate() {
+            if self.covers(i) {
+                for c in 0..3 {
+                    pixel[c] = (pixel[c] as u16 * color[c] as u16 / 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+// Reads the metal and its mask from mat.yml. The mask is a texture in the
+// material folder, pixels from 128 up are metal.
+pub fn metal_coverage(
+    material_dir: &Path,
+    mat_yml: &MatYml,
+    width: u32,
+    height: u32,
+) -> CommandResult<Option<MetalCoverage>> {
+    let Some(hcm) = &mat_yml.hcm else {
+        return Ok(None);
+    };
+    let Some(metal) = hcm.metal else {
+        return Ok(None);
+    };
+
+    let size = (width * height) as usize;
+    let covered = match hcm.mask.as_deref().filter(|mask| !mask.trim().is_empty()) {
+        None => vec![true; size],
+        Some(mask) => {
+            let mask_path = material_dir.join(mask);
+            if !mask_path.is_file() {
+                return Err(Error::not_found(&mask_path, "Metal mask does not exist"));
+            }
+
+            let img = image::open(&mask_path)
+                .map_err(|e| Error::parse(&mask_path, e))?
+                .to_luma8();
+            let img = match img.dimensions() == (width, height) {
+                true => img,
+                false => image::imageops::resize(&img, width, height, FilterType::Nearest),
+            };
+            img.pixels().map(|p| p[0] >= 128).collect()
+        }
+    };
+
+    Ok(Some(MetalCoverage { metal, covered }))
+}
+
+#[derive(serde::Serialize)]
+pub struct MetalInfo {
+    pub metal: Hcm,
+    pub name: &'static str,
+    pub index: u8,
+    pub n: [f32; 3],
+    pub k: [f32; 3],
+    pub f0: [f32; 3],
+    // `#rrggbb` for swatches
+    pub color: String,
+}
+
+#[tauri::command]
+pub fn list_metals() -> Vec<MetalInfo> {
+    METAL_PRESETS
+        .iter()
+        .map(|preset| {
+            let [r, g, b] = preset.color();
+            MetalInfo {
+                metal: preset.metal,
+                name: preset.name,
+                index: preset.metal.index(),
+                n: preset.n,
+                k: preset.k,
+                f0: preset.f0(),
+                color: format!("#{:02x}{:02x}{:02x}", r, g, b),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::interface::structs::DefaultsHcm;
+
+    #[test]
+    fn preset_indices() {
+        let expected = [
+            (Hcm::Iron, 230),
+            (Hcm::Gold, 231),
+            (Hcm::Aluminum, 232),
+            (Hcm::Chrome, 233),
+            (Hcm::Copper, 234),
+            (Hcm::Lead, 235),
+            (Hcm::Platinum, 236),
+            (Hcm::Silver, 237),
+        ];
+        for (metal_preset, (metal, index)) in METAL_PRESETS.iter().zip(expected) {
+            assert_eq!(metal_preset.metal, metal);
+            assert_eq!(metal.index(), index);
+            assert_eq!(preset(metal).map(|p| p.name), Some(metal_preset.name));
+        }
+
+        assert_eq!(Hcm::CustomF0.index(), 0);
+        assert!(preset(Hcm::CustomF0).is_none());
+    }
+
+    #[test]
+    fn iron_reflectance() {
+        let iron = preset(Hcm::Iron).unwrap();
+        for (f0, expected) in iron.f0().into_iter().zip([0.53123, 0.51236, 0.49583]) {
+            assert!((f0 - expected).abs() < 1e-4, "{} != {}", f0, expected);
+        }
+        assert_eq!(iron.color(), [193, 190, 187]);
+        assert_eq!(preset(Hcm::Gold).unwrap().color(), [249, 228, 164]);
+    }
+
+    fn masked_coverage(metal: Hcm) -> MetalCoverage {
+        let dir = tempfile::tempdir().unwrap();
+        // Only pixels from 128 up are metal
+        GrayImage::from_raw(2, 2, vec![255, 0, 128, 127])
+            .unwrap()
+            .save(dir.path().join("mask.png"))
+            .unwrap();
+
+        let mut mat_yml = MatYml::new();
+        mat_yml.hcm = Some(DefaultsHcm {
+            metal: Some(metal),
+            mask: Some("mask.png".to_string()),
+        });
+        metal_coverage(dir.path(), &mat_yml, 2, 2).unwrap().unwrap()
+    }
+
+    #[test]
+    fn masked_metal() {
+        let coverage = masked_coverage(Hcm::Gold);
+        let f0 = GrayImage::from_pixel(2, 2, image::Luma([100]));
+
+        assert_eq!(coverage.apply_hcm(None, 2, 2).into_raw(), [231, 0, 231, 0]);
+        assert_eq!(
+            coverage.apply_f0(Some(f0.clone())).unwrap().into_raw(),
+            [0, 100, 0, 100]
+        );
+
+        // Custom f0 clears the hcm under the mask and keeps the reflectance
+        let custom = masked_coverage(Hcm::CustomF0);
+        let hcm = GrayImage::from_pixel(2, 2, image::Luma([233]));
+        assert_eq!(
+            custom.apply_hcm(Some(hcm), 2, 2).into_raw(),
+            [0, 233, 0, 233]
+        );
+        assert_eq!(custom.apply_f0(Some(f0)).unwrap().into_raw(), [100; 4]);
+    }
+}
@@ -0,0 +1,362 @@
+// mat.yml and project.yml carry a `schema_version`, files written before it
+// existed are version 0. Loading runs the migrations between a file's version
+// and the current one on the raw document before reading it into the typed
+// struct, so older files keep opening. The upgraded version is written on the
+// next save.
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+use super::{
+    error::{CommandResult, Error},
+    interface::structs::{DefaultsColor, DefaultsGrayscale, DefaultsHeight, MatYml},
+    normal_map::KernelSize,
+    project::structs::ProjectYml,
+    template::apply_templates,
+};
+
+pub const MAT_YML_VERSION: u32 = 1;
+pub const PROJECT_YML_VERSION: u32 = 1;
+
+type Migration = fn(&mut Mapping);
+
+// The migration at index `n` upgrades a version `n` document to `n + 1`
+const MAT_YML_MIGRATIONS: [Migration; MAT_YML_VERSION as usize] = [mat_yml_v1];
+const PROJECT_YML_MIGRATIONS: [Migration; PROJECT_YML_VERSION as usize] = [project_yml_v1];
+
+// Version 1 only added `schema_version`
+fn mat_yml_v1(_doc: &mut Mapping) {}
+
+// create_project used to misspell `description`
+fn project_yml_v1(doc: &mut Mapping) {
+    if let Some(description) = doc.remove("desription") {
+        doc.entry("description".into()).or_insert(description);
+    }
+    if !doc.contains_key("input") {
+        let mut input = Mapping::new();
+        input.insert("format".into(), "raw".into());
+        doc.insert("input".into(), Value::Mapping(input));
+    }
+}
+
+// create_project also indented `input` with a tab, which isn't valid yml
+fn replace_tab_indents(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let tabs = line.len() - line.trim_start_matches('\t').len();
+            format!("{}{}", "  ".repeat(tabs), &line[tabs..])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn read_version(path: &Path, doc: &Mapping, current: u32) -> CommandResult<u32> {
+    let version = match doc.get("schema_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                Error::invalid_field(path, "schema_version", "Must be a whole number")
+            })?,
+    };
+
+    if version > current {
+        return Err(Error::invalid_field(
+            path,
+            "schema_version",
+            format!(
+                "Version {} is newer than this version of Pixel Toolkit supports ({})",
+                version, current
+            ),
+        ));
+    }
+    Ok(version)
+}
+
+// Parses `text` and upgrades it to the current version, without reading it
+// into the typed struct yet
+fn migrate(path: &Path, text: &str, migrations: &[Migration]) -> CommandResult<Mapping> {
+    let mut doc: Value = serde_yaml::from_str(text)
+        .or_else(|e| match text.contains("\n\t") {
+            true => serde_yaml::from_str(&replace_tab_indents(text)),
+            false => Err(e),
+        })
+        .map_err(|e| Error::parse(path, e))?;
+
+    // An empty file is an empty document
+    if doc.is_null() {
+        doc = Value::Mapping(Mapping::new());
+    }
+    let Value::Mapping(mut map) = doc else {
+        return Err(Error::invalid_field(
+            path,
+            ".",
+            "Expected a mapping of keys",
+        ));
+    };
+
+    let current = migrations.len() as u32;
+    let version = read_version(path, &map, current)?;
+    for migration in &migrations[version as usize..] {
+        migration(&mut map);
+    }
+    map.insert("schema_version".into(), current.into());
+
+    Ok(map)
+}
+
+fn deserialize<T: DeserializeOwned>(path: &Path, map: Mapping) -> CommandResult<T> {
+    serde_path_to_error::deserialize(Value::Mapping(map)).map_err(|e| {
+        let field = e.path().to_string();
+        Error::invalid_field(path, field, e.into_inner())
+    })
+}
+
+// A mat.yml or template as a raw document, before its templates are merged in
+pub fn migrate_mat_yml(path: &Path, text: &str) -> CommandResult<Mapping> {
+    migrate(path, text, &MAT_YML_MIGRATIONS)
+}
+
+pub fn parse_mat_yml(path: &Path, text: &str) -> CommandResult<MatYml> {
+    let doc = apply_templates(path, migrate_mat_yml(path, text)?)?;
+    let mat_yml: MatYml = deserialize(path, doc)?;
+    validate_mat_yml(&mat_yml)
+        .map_err(|(field, message)| Error::invalid_field(path, field, message))?;
+    Ok(mat_yml)
+}
+
+pub fn parse_project_yml(path: &Path, text: &str) -> CommandResult<ProjectYml> {
+    let project_yml: ProjectYml = deserialize(path, migrate(path, text, &PROJECT_YML_MIGRATIONS)?)?;
+    validate_project_yml(&project_yml)
+        .map_err(|(field, message)| Error::invalid_field(path, field, message))?;
+    Ok(project_yml)
+}
+
+// Values that parse but can't be used, as (field, message)
+fn validate_mat_yml(mat_yml: &MatYml) -> Result<(), (String, String)> {
+    let grayscale: [(&str, &Option<DefaultsGrayscale>); 9] = [
+        ("opacity", &mat_yml.opacity),
+        ("occlusion", &mat_yml.occlusion),
+        ("smooth", &mat_yml.smooth),
+        ("rough", &mat_yml.rough),
+        ("porosity", &mat_yml.porosity),
+        ("metal", &mat_yml.metal),
+        ("f0", &mat_yml.f0),
+        ("sss", &mat_yml.sss),
+        ("emissive", &mat_yml.emissive),
+    ];
+    for (name, defaults) in grayscale {
+        let Some(defaults) = defaults else {
+            continue;
+        };
+        if defaults
+            .value
+            .is_some_and(|value| !(0.0..=255.0).contains(&value))
+        {
+            return Err((
+                format!("{}.value", name),
+                "Must be between 0 and 255".to_string(),
+            ));
+        }
+        for (key, value) in [("scale", defaults.scale), ("shift", defaults.shift)] {
+            if value.is_some_and(|value| !value.is_finite()) {
+                return Err((format!("{}.{}", name, key), "Must be a number".to_string()));
+            }
+        }
+    }
+
+    if let Some(color) = &mat_yml.color {
+        if let Err((field, message)) = validate_color(color) {
+            return Err((format!("color.{}", field), message));
+        }
+    }
+
+    if let Some(height) = &mat_yml.height {
+        if let Err((field, message)) = validate_height(height) {
+            return Err((format!("height.{}", field), message));
+        }
+    }
+
+    if let Some(normal) = &mat_yml.normal {
+        if normal.method > KernelSize::MAX_METHOD {
+            return Err((
+                "normal.method".to_string(),
+                format!("Must be between 0 and {}", KernelSize::MAX_METHOD),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_height(height: &DefaultsHeight) -> Result<(), (&'static str, String)> {
+    if height
+        .value
+        .is_some_and(|value| !(0.0..=255.0).contains(&value))
+    {
+        return Err(("value", "Must be between 0 and 255".to_string()));
+    }
+    for (key, value) in [("scale", height.scale), ("shift", height.shift)] {
+        if value.is_some_and(|value| !value.is_finite()) {
+            return Err((key, "Must be a number".to_string()));
+        }
+    }
+    for (key, value) in [
+        ("blur_radius", height.blur_radius),
+        ("edge_fade", height.edge_fade),
+    ] {
+        if value.is_some_and(|value| !(value.is_finite() && value >= 0.0)) {
+            return Err((key, "Must be 0 or above".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_color(color: &DefaultsColor) -> Result<(), (&'static str, String)> {
+    if color
+        .tint
+        .as_deref()
+        .is_some_and(|tint| DefaultsColor::parse_tint(tint).is_none())
+    {
+        return Err(("tint", "Must be a colour like #ffaa00".to_string()));
+    }
+
+    for (key, value) in [
+        ("red", color.red),
+        ("green", color.green),
+        ("blue", color.blue),
+    ] {
+        if value.is_some_and(|value| !(0.0..=255.0).contains(&value)) {
+            return Err((key, "Must be between 0 and 255".to_string()));
+        }
+    }
+    for (key, value) in [
+        ("saturation", color.saturation),
+        ("contrast", color.contrast),
+    ] {
+        if value.is_some_and(|value| !(value.is_finite() && value >= 0.0)) {
+            return Err((key, "Must be 0 or above".to_string()));
+        }
+    }
+    if color
+        .brightness
+        .is_some_and(|value| !(-1.0..=1.0).contains(&value))
+    {
+        return Err(("brightness", "Must be between -1 and 1".to_string()));
+    }
+    if color.hue_shift.is_some_and(|value| !value.is_finite()) {
+        return Err(("hue_shift", "Must be a number".to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate_project_yml(project_yml: &ProjectYml) -> Result<(), (String, String)> {
+    if project_yml.name.trim().is_empty() {
+        return Err(("name".to_string(), "Must not be empty".to_string()));
+    }
+
+    for (i, profile) in project_yml.profiles.iter().flatten().enumerate() {
+        for (key, size) in [
+            ("block_texture_size", profile.block_texture_size),
+            ("item_texture_size", profile.item_texture_size),
+            ("texture_scale", profile.texture_scale.map(i16::from)),
+        ] {
+            if size.is_some_and(|size| size <= 0) {
+                return Err((
+                    format!("profiles[{}].{}", i, key),
+                    "Must be above 0".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::project::structs::InputFormat;
+
+    fn project_yml(text: &str) -> CommandResult<ProjectYml> {
+        parse_project_yml(Path::new("project.yml"), text)
+    }
+
+    // The field an InvalidField error points at
+    fn invalid_field<T>(result: CommandResult<T>) -> String {
+        match result {
+            Err(Error::InvalidField { field, .. }) => field,
+            Err(e) => panic!("expected an invalid field, got {}", e),
+            Ok(_) => panic!("expected an invalid field"),
+        }
+    }
+
+    #[test]
+    fn unversioned_files_are_upgraded() {
+        let project_yml = project_yml("name: Old\ndesription: Shiny\n").unwrap();
+        assert_eq!(project_yml.schema_version, PROJECT_YML_VERSION);
+        assert_eq!(project_yml.description.as_deref(), Some("Shiny"));
+        assert_eq!(project_yml.input.format, InputFormat::Raw);
+
+        let mat_yml = migrate_mat_yml(Path::new("mat.yml"), "smooth:\n  value: 3\n").unwrap();
+        assert_eq!(mat_yml["schema_version"], Value::from(MAT_YML_VERSION));
+        assert_eq!(
+            migrate_mat_yml(Path::new("mat.yml"), "").unwrap()["schema_version"],
+            Value::from(MAT_YML_VERSION)
+        );
+    }
+
+    #[test]
+    fn tab_indents_are_repaired() {
+        let project_yml =
+            project_yml("name: Old\ndesription: ''\ninput:\n\tformat: labpbr-1.3\n").unwrap();
+        assert_eq!(project_yml.input.format, InputFormat::LabPbr13);
+
+        // Tabs inside values are left alone
+        assert_eq!(
+            replace_tab_indents("\t\tkey: a\tb\nname: c"),
+            "    key: a\tb\nname: c"
+        );
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let newer = format!("schema_version: {}\nname: New\n", PROJECT_YML_VERSION + 1);
+        let Err(Error::InvalidField { field, message, .. }) = project_yml(&newer) else {
+            panic!("a newer project.yml was read");
+        };
+        assert_eq!(field, "schema_version");
+        assert!(message.starts_with("Version 2 is newer"), "{message}");
+
+        assert_eq!(
+            invalid_field(project_yml("schema_version: -1\nname: New\n")),
+            "schema_version"
+        );
+        assert_eq!(
+            invalid_field(project_yml("schema_version: one\nname: New\n")),
+            "schema_version"
+        );
+    }
+
+    #[test]
+    fn type_errors_point_at_the_field() {
+        let profile = "name: P\nprofiles:\n  - name: a\n    edition: Java\n    format: high\n";
+        assert_eq!(invalid_field(project_yml(profile)), "profiles[0].format");
+        assert_eq!(invalid_field(project_yml("name: P\ninput: raw\n")), "input");
+        assert_eq!(invalid_field(project_yml("- name\n")), ".");
+
+        let mat_yml = |text| parse_mat_yml(Path::new("mat.yml"), text);
+        assert_eq!(
+            invalid_field(mat_yml("normal:\n  curve_x: steep\n")),
+            "normal.curve_x"
+        );
+        assert_eq!(
+            invalid_field(mat_yml("rough:\n  value: 300\n")),
+            "rough.value"
+        );
+    }
+}
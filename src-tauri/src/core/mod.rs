@@ -1,7 +1,18 @@
+pub mod codec;
+pub mod error;
+pub mod events;
+pub mod export;
 pub mod home;
 pub mod image_process;
+pub mod import;
 pub mod interface;
+pub mod metals;
+pub mod migration;
 pub mod project;
+pub mod schema;
+pub mod template;
 
 pub mod normal_map;
+pub mod store;
 pub mod utils;
+pub mod yaml_patch;
@@ -14,6 +14,24 @@ pub enum KernelSize {
     Variance,
 }
 
+impl KernelSize {
+    // The highest `method` index that picks a kernel of its own
+    pub const MAX_METHOD: usize = 5;
+
+    // Maps the `method` index stored in mat.yml to a kernel
+    pub fn from_method(method: usize) -> Self {
+        match method {
+            0 => KernelSize::Three,
+            1 => KernelSize::Five,
+            2 => KernelSize::Nine,
+            3 => KernelSize::Low,
+            4 => KernelSize::High,
+            5 => KernelSize::Variance,
+            _ => KernelSize::Three,
+        }
+    }
+}
+
 struct Kernel {
     x: Arc<Vec<Vec<f32>>>,
     y: Arc<Vec<Vec<f32>>>,
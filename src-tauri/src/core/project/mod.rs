@@ -1,15 +1,20 @@
+pub mod profiles;
 pub mod structs;
 
 use std::{
     fs::{self},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
-use structs::{FileTree, ProjectYml};
+use structs::{FileTree, InputFormat, ProjectYml};
 
 use super::{
-    home::{get_projects_vec, remove_project, Project},
-    utils::get_config_dir,
+    error::{CommandResult, Error},
+    events::EventSink,
+    home::{get_projects_vec, modify_projects, project_not_found, remove_project_entry},
+    migration::parse_project_yml,
+    schema::write_schemas,
+    store::{self, FileLock},
 };
 
 #[derive(serde::Serialize)]
@@ -65,14 +70,14 @@ fn build_file_tree(path: &Path) -> Option<FileTree> {
 }
 
 #[tauri::command]
-pub fn get_dirs(project_id: String, app: tauri::AppHandle) -> Result<String, String> {
+pub fn get_dirs(project_id: String, app: tauri::AppHandle) -> CommandResult<String> {
     let mut projects = get_projects_vec(&app);
     let project = projects
         .iter_mut()
         .find(|p| p.id == project_id)
         .ok_or_else(|| {
-            remove_project(project_id.clone(), app.clone());
-            "Project not found".to_string()
+            let _ = remove_project_entry(&project_id, &app);
+            project_not_found(&project_id, &app)
         })?;
 
     update_project_modified_date(project_id.clone(), &app)?;
@@ -80,8 +85,13 @@ pub fn get_dirs(project_id: String, app: tauri::AppHandle) -> Result<String, Str
     let path = Path::new(&project.path);
     let project_yml_path = path.join("project.yml");
     if !project_yml_path.exists() {
-        remove_project(project_id, app);
-        return Err("Project file not found".to_string());
+        let _ = remove_project_entry(&project_id, &app);
+        return Err(Error::not_found(project_yml_path, "Project file not found"));
+    }
+
+    // Keeps editor completion in step with this version's mat.yml
+    if let Err(e) = write_schemas(path) {
+        eprintln!("Failed to write schemas: {}", e);
     }
 
     let file_tree = build_file_tree(path).unwrap_or_else(|| FileTree {
@@ -95,83 +105,144 @@ pub fn get_dirs(project_id: String, app: tauri::AppHandle) -> Result<String, Str
         project_path: project.path.clone(),
     };
 
-    serde_json::to_string(&response).map_err(|_| "Error serializing response".to_string())
+    serde_json::to_string(&response).map_err(|e| Error::other(path, e))
 }
 
-fn update_project_modified_date(project_id: String, app: &tauri::AppHandle) -> Result<(), String> {
-    let current_date = chrono::Utc::now();
-
-    let config_dir = get_config_dir(app);
-    let projects_yml_path = Path::new(&config_dir).join("projects.yml");
-
-    let mut projects = get_projects_vec(app);
-    for p in projects.iter_mut() {
-        if p.id == project_id {
-            p.date_modified = current_date.to_string();
-        }
-    }
+// Walks up from a material folder to the folder holding project.yml
+pub fn find_project_dir(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join("project.yml").exists())
+        .map(Path::to_path_buf)
+}
 
-    let updated_content = serde_yaml::to_string(&projects)
-        .map_err(|e| format!("Failed to serialize project file: {}", e))?;
+// Falls back to separate channel files when project.yml can't be read
+pub fn get_input_format(path: &Path) -> InputFormat {
+    find_project_dir(path)
+        .and_then(|dir| load_project_yml(&dir).ok())
+        .map(|project_yml| project_yml.input.format)
+        .unwrap_or_default()
+}
 
-    fs::write(&projects_yml_path, updated_content)
-        .map_err(|e| format!("Failed to write project file: {}", e))
+pub fn load_project_yml(project_dir: &Path) -> CommandResult<ProjectYml> {
+    let project_yml_path = project_dir.join("project.yml");
+    store::read_with(&project_yml_path, |text| {
+        parse_project_yml(&project_yml_path, text)
+    })
 }
 
-pub fn update_project(project_id: String, project_yml: ProjectYml, app: tauri::AppHandle) -> bool {
-    let config_dir = get_config_dir(&app);
-    let projects_yml_path = Path::new(&config_dir).join("projects.yml");
+fn update_project_modified_date(project_id: String, sink: &dyn EventSink) -> CommandResult<()> {
+    let current_date = chrono::Utc::now();
 
-    let mut projects = get_projects_vec(&app);
-    for p in projects.iter_mut() {
-        if p.id == project_id {
-            p.name = project_yml.name.clone();
-            if p.description == Some("".to_owned()) {
-                p.description = project_yml.description.clone();
+    modify_projects(sink, |projects| {
+        for p in projects.iter_mut() {
+            if p.id == project_id {
+                p.date_modified = current_date.to_string();
             }
         }
-    }
+        Ok(())
+    })
+}
 
-    let filtered_projects: Vec<Project> = projects
-        .clone()
-        .into_iter()
-        .filter(|p| p.id == project_id)
-        .collect();
-    if filtered_projects.len() == 0 {
-        return false;
-    }
+// Writes a registered project's project.yml and refreshes its entry in
+// projects.yml. NotFound when the project isn't in the list.
+pub fn update_project(
+    project_id: String,
+    project_yml: &ProjectYml,
+    sink: &dyn EventSink,
+) -> CommandResult<()> {
+    modify_projects(sink, |projects| {
+        let project = projects
+            .iter_mut()
+            .find(|p| p.id == project_id)
+            .ok_or_else(|| project_not_found(&project_id, sink))?;
+
+        project.name = project_yml.name.clone();
+        if project.description == Some("".to_owned()) {
+            project.description = project_yml.description.clone();
+        }
 
-    let project = &filtered_projects[0];
-    let path = Path::new(&project.path);
-    let project_yml_path = path.join("project.yml");
+        write_project_yml(Path::new(&project.path), project_yml)
+    })
+}
 
-    let updated_project_yml = serde_yaml::to_string(&project_yml)
-        .map_err(|e| {
-            format!("Failed to serializse project.yml: {}", e);
-            return false;
-        })
-        .unwrap();
+// Writes project.yml, keeping the profiles already on disk when `project_yml`
+// has none so metadata updates (e.g. from an import) don't drop them
+pub fn write_project_yml(project_dir: &Path, project_yml: &ProjectYml) -> CommandResult<()> {
+    let project_yml_path = project_dir.join("project.yml");
+    let _lock = FileLock::acquire(&project_yml_path)?;
+
+    let existing_profiles = match &project_yml.profiles {
+        Some(_) => None,
+        None => load_project_yml(project_dir)
+            .ok()
+            .and_then(|existing| existing.profiles),
+    };
+    match existing_profiles {
+        Some(profiles) => store::write_yaml(
+            &project_yml_path,
+            &ProjectYml {
+                profiles: Some(profiles),
+                ..project_yml.clone()
+            },
+        ),
+        None => store::write_yaml(&project_yml_path, project_yml),
+    }
+}
 
-    fs::write(&project_yml_path, &updated_project_yml)
-        .map_err(|e| {
-            format!("Failed to write project file: {}", e);
-            false
-        })
-        .unwrap();
+// Runs `f` on project.yml and saves the result, holding the lock in between
+pub fn modify_project_yml<R>(
+    project_dir: &Path,
+    f: impl FnOnce(&mut ProjectYml) -> CommandResult<R>,
+) -> CommandResult<R> {
+    let project_yml_path = project_dir.join("project.yml");
+    let _lock = FileLock::acquire(&project_yml_path)?;
 
-    let updated_content = serde_yaml::to_string(&projects)
-        .map_err(|e| {
-            format!("Failed to serialize project file: {}", e);
-            false
-        })
-        .unwrap();
+    let mut project_yml = load_project_yml(project_dir)?;
+    let result = f(&mut project_yml)?;
+    store::write_yaml(&project_yml_path, &project_yml)?;
 
-    fs::write(&projects_yml_path, updated_content)
-        .map_err(|e| {
-            format!("Failed to write project file: {}", e);
-            false
-        })
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_survive_metadata_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("project.yml"),
+            "name: Old\nprofiles:\n  - name: Java\n    edition: Java\n    format: 34\n",
+        )
         .unwrap();
 
-    true
+        let renamed = ProjectYml::new("New".to_string(), None, InputFormat::LabPbr13);
+        write_project_yml(dir.path(), &renamed).unwrap();
+
+        let project_yml = load_project_yml(dir.path()).unwrap();
+        assert_eq!(project_yml.name, "New");
+        assert_eq!(project_yml.input.format, InputFormat::LabPbr13);
+        let names: Vec<_> = project_yml
+            .profiles
+            .iter()
+            .flatten()
+            .map(|p| &p.name)
+            .collect();
+        assert_eq!(names, vec!["Java"]);
+
+        // Profiles that are given replace the ones on disk
+        let cleared = ProjectYml {
+            profiles: Some(Vec::new()),
+            ..renamed
+        };
+        write_project_yml(dir.path(), &cleared).unwrap();
+        assert_eq!(
+            load_project_yml(dir.path())
+                .unwrap()
+                .profiles
+                .map(|p| p.len()),
+            Some(0)
+        );
+    }
 }
@@ -0,0 +1,290 @@
+// Commands for the publish profiles kept in project.yml. Profiles are found
+// by name, which has to be unique within a project.
+
+use std::path::Path;
+
+use super::{
+    load_project_yml, modify_project_yml,
+    structs::{GameEdition, Profile},
+};
+use crate::core::{
+    error::{CommandResult, Error},
+    export::get_project_path,
+};
+
+// Bedrock manifests only know these `format_version`s
+const BEDROCK_FORMATS: [i8; 2] = [1, 2];
+
+// The first field that stops `profile` from being published, as
+// (field, message)
+pub fn validate_profile(profile: &Profile) -> Result<(), (String, String)> {
+    if profile.name.trim().is_empty() {
+        return Err(("name".to_string(), "Must not be empty".to_string()));
+    }
+
+    for (key, size) in [
+        ("block_texture_size", profile.block_texture_size),
+        ("item_texture_size", profile.item_texture_size),
+    ] {
+        if size.is_some_and(|size| size <= 0 || !(size as u16).is_power_of_two()) {
+            return Err((
+                key.to_string(),
+                "Must be a power of two, e.g. 16, 32 or 64".to_string(),
+            ));
+        }
+    }
+    if profile.texture_scale.is_some_and(|scale| scale <= 0) {
+        return Err(("texture_scale".to_string(), "Must be above 0".to_string()));
+    }
+
+    match profile.edition {
+        GameEdition::Java if profile.format < 1 => Err((
+            "format".to_string(),
+            "Java pack formats start at 1".to_string(),
+        )),
+        GameEdition::Bedrock if !BEDROCK_FORMATS.contains(&profile.format) => Err((
+            "format".to_string(),
+            "Bedrock manifests use format 1 or 2".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_profile(project_dir: &Path, profile: &Profile) -> CommandResult<()> {
+    validate_profile(profile).map_err(|(field, message)| {
+        Error::invalid_field(project_dir.join("project.yml"), field, message)
+    })
+}
+
+fn find_index(project_dir: &Path, profiles: &[Profile], name: &str) -> CommandResult<usize> {
+    profiles.iter().position(|p| p.name == name).ok_or_else(|| {
+        Error::not_found(
+            project_dir.join("project.yml"),
+            format!("Profile {} not found", name),
+        )
+    })
+}
+
+// `skip` is the profile being renamed, which may keep its own name
+fn ensure_unique(
+    project_dir: &Path,
+    profiles: &[Profile],
+    name: &str,
+    skip: Option<usize>,
+) -> CommandResult<()> {
+    let taken = profiles
+        .iter()
+        .enumerate()
+        .any(|(i, p)| Some(i) != skip && p.name == name);
+
+    match taken {
+        true => Err(Error::conflict(
+            project_dir.join("project.yml"),
+            format!("A profile called {} already exists", name),
+        )),
+        false => Ok(()),
+    }
+}
+
+// "Java copy", then "Java copy 2", "Java copy 3", ...
+fn copy_name(profiles: &[Profile], name: &str) -> String {
+    (1..)
+        .map(|n| match n {
+            1 => format!("{} copy", name),
+            n => format!("{} copy {}", name, n),
+        })
+        .find(|candidate| profiles.iter().all(|p| &p.name != candidate))
+        .unwrap_or_default()
+}
+
+fn modify_profiles<R>(
+    project_dir: &Path,
+    f: impl FnOnce(&mut Vec<Profile>) -> CommandResult<R>,
+) -> CommandResult<R> {
+    modify_project_yml(project_dir, |project_yml| {
+        f(project_yml.profiles.get_or_insert_with(Vec::new))
+    })
+}
+
+#[tauri::command]
+pub fn list_profiles(project_id: String, app: tauri::AppHandle) -> CommandResult<Vec<Profile>> {
+    let project_dir = get_project_path(&project_id, &app)?;
+    let project_yml = load_project_yml(&project_dir)?;
+
+    Ok(project_yml.profiles.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn create_profile(
+    project_id: String,
+    profile: Profile,
+    app: tauri::AppHandle,
+) -> CommandResult<Profile> {
+    let project_dir = get_project_path(&project_id, &app)?;
+    check_profile(&project_dir, &profile)?;
+
+    modify_profiles(&project_dir, |profiles| {
+        ensure_unique(&project_dir, profiles, &profile.name, None)?;
+        profiles.push(profile.clone());
+        Ok(profile)
+    })
+}
+
+// Replaces the profile called `name`, which can also rename it
+#[tauri::command]
+pub fn update_profile(
+    project_id: String,
+    name: String,
+    profile: Profile,
+    app: tauri::AppHandle,
+) -> CommandResult<Profile> {
+    let project_dir = get_project_path(&project_id, &app)?;
+    check_profile(&project_dir, &profile)?;
+
+    modify_profiles(&project_dir, |profiles| {
+        let index = find_index(&project_dir, profiles, &name)?;
+        ensure_unique(&project_dir, profiles, &profile.name, Some(index))?;
+        profiles[index] = profile.clone();
+        Ok(profile)
+    })
+}
+
+#[tauri::command]
+pub fn duplicate_profile(
+    project_id: String,
+    name: String,
+    new_name: Option<String>,
+    app: tauri::AppHandle,
+) -> CommandResult<Profile> {
+    let project_dir = get_project_path(&project_id, &app)?;
+
+    modify_profiles(&project_dir, |profiles| {
+        let index = find_index(&project_dir, profiles, &name)?;
+        let new_name = match new_name {
+            Some(new_name) => {
+                ensure_unique(&project_dir, profiles, &new_name, None)?;
+                new_name
+            }
+            None => copy_name(profiles, &name),
+        };
+
+        let copy = Profile {
+            name: new_name,
+            ..profiles[index].clone()
+        };
+        check_profile(&project_dir, &copy)?;
+        profiles.push(copy.clone());
+        Ok(copy)
+    })
+}
+
+#[tauri::command]
+pub fn delete_profile(
+    project_id: String,
+    name: String,
+    app: tauri::AppHandle,
+) -> CommandResult<()> {
+    let project_dir = get_project_path(&project_id, &app)?;
+
+    modify_profiles(&project_dir, |profiles| {
+        let index = find_index(&project_dir, profiles, &name)?;
+        profiles.remove(index);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, edition: GameEdition, format: i8) -> Profile {
+        Profile {
+            edition,
+            format,
+            name: name.to_string(),
+            description: None,
+            block_texture_size: None,
+            item_texture_size: None,
+            texture_scale: None,
+            auto_level_height: None,
+            bake_occlusion_texture: None,
+            output_format: None,
+        }
+    }
+
+    fn invalid_field(profile: &Profile) -> Option<String> {
+        validate_profile(profile).err().map(|(field, _)| field)
+    }
+
+    #[test]
+    fn profiles_are_validated() {
+        let java = profile("Java", GameEdition::Java, 34);
+        assert_eq!(invalid_field(&java), None);
+        assert_eq!(
+            invalid_field(&profile("RTX", GameEdition::Bedrock, 2)),
+            None
+        );
+
+        let invalid = [
+            (profile(" ", GameEdition::Java, 34), "name"),
+            (profile("Java", GameEdition::Java, 0), "format"),
+            (profile("RTX", GameEdition::Bedrock, 3), "format"),
+            (
+                Profile {
+                    block_texture_size: Some(24),
+                    ..java.clone()
+                },
+                "block_texture_size",
+            ),
+            (
+                Profile {
+                    item_texture_size: Some(-16),
+                    ..java.clone()
+                },
+                "item_texture_size",
+            ),
+            (
+                Profile {
+                    texture_scale: Some(0),
+                    ..java.clone()
+                },
+                "texture_scale",
+            ),
+        ];
+        for (profile, field) in invalid {
+            assert_eq!(invalid_field(&profile).as_deref(), Some(field));
+        }
+    }
+
+    #[test]
+    fn copies_get_a_free_name() {
+        let mut profiles = vec![profile("Java", GameEdition::Java, 34)];
+        assert_eq!(copy_name(&profiles, "Java"), "Java copy");
+
+        profiles.push(profile("Java copy", GameEdition::Java, 34));
+        profiles.push(profile("Java copy 2", GameEdition::Java, 34));
+        assert_eq!(copy_name(&profiles, "Java"), "Java copy 3");
+    }
+
+    #[test]
+    fn renames_must_stay_unique() {
+        let project_dir = Path::new("packs/stone");
+        let profiles = vec![
+            profile("Java", GameEdition::Java, 34),
+            profile("RTX", GameEdition::Bedrock, 2),
+        ];
+
+        // A profile can keep its own name
+        assert!(ensure_unique(project_dir, &profiles, "Java", Some(0)).is_ok());
+        assert!(ensure_unique(project_dir, &profiles, "Java 1.21", Some(0)).is_ok());
+
+        match ensure_unique(project_dir, &profiles, "RTX", Some(0)) {
+            Err(Error::Conflict { path, message }) => {
+                assert_eq!(Path::new(&path), project_dir.join("project.yml"));
+                assert_eq!(message, "A profile called RTX already exists");
+            }
+            _ => panic!("renaming onto another profile was allowed"),
+        }
+        assert!(ensure_unique(project_dir, &profiles, "Java", None).is_err());
+    }
+}
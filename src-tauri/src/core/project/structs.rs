@@ -1,3 +1,7 @@
+use std::collections::BTreeMap;
+
+use crate::core::migration::PROJECT_YML_VERSION;
+
 // use serde::{Deserialize, Deserializer};
 
 // For unwrapping optional values elegantly
@@ -11,35 +15,195 @@
 //     Ok(val)
 // }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// Project settings, kept in project.yml at the project root
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone)]
 pub struct ProjectYml {
+    /// Version of the project.yml layout, older files are upgraded when loaded
+    #[serde(default)]
+    pub schema_version: u32,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<String>,
     pub input: Input,
     // #[serde(deserialize_with = "null_to_default")]
+    /// Packs that can be published from this project
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub profiles: Option<Vec<Profile>>,
+    /// How packs are imported into this project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import: Option<ImportRules>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+impl ProjectYml {
+    // A project.yml at the current schema version with no profiles yet
+    pub fn new(name: String, description: Option<String>, format: InputFormat) -> Self {
+        ProjectYml {
+            schema_version: PROJECT_YML_VERSION,
+            name,
+            description,
+            tags: None,
+            input: Input { format },
+            profiles: None,
+            import: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone)]
 pub struct Input {
-    pub format: String,
+    /// How textures are laid out inside material folders
+    pub format: InputFormat,
+}
+
+/// How the textures inside a material folder are laid out
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    Clone,
+    Copy,
+    PartialEq,
+    Debug,
+    Default,
+)]
+pub enum InputFormat {
+    /// Separate channel files (color.png, normal.png, smooth.png, ...)
+    #[default]
+    #[serde(rename = "raw")]
+    Raw,
+    /// Packed `_n.png` / `_s.png` with AO in the normal's blue channel
+    #[serde(rename = "labpbr-1.3")]
+    LabPbr13,
+    /// Packed `_n.png` / `_s.png` with the normal's Z in blue
+    #[serde(rename = "labpbr-1.2")]
+    LabPbr12,
+    /// SEUS style `_s.png` with smoothness, metalness and emission in RGB
+    #[serde(rename = "oldpbr")]
+    OldPbr,
+}
+
+/// How the zip and folder importers treat the files of a pack. Globs are
+/// matched against paths relative to the pack root, e.g.
+/// `assets/minecraft/textures/block/stone.png`.
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone)]
+#[serde(default)]
+pub struct ImportRules {
+    /// Only textures matching one of these are converted, all when empty
+    pub include: Vec<String>,
+    /// Textures matching any of these are copied without converting them
+    pub exclude: Vec<String>,
+    /// File name suffix to channel, e.g. `_n: normal` or `_e: emissive`.
+    /// `normal` and `specular` are decoded with the input format, anything
+    /// else is saved as that channel's file.
+    pub suffixes: BTreeMap<String, String>,
+    /// The first matching folder rule decides what happens to its files
+    pub directories: Vec<DirectoryRule>,
+}
+
+/// What happens to the files in the folders matching `path`
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone)]
+pub struct DirectoryRule {
+    /// Glob matched against each folder a file sits in, e.g. `**/gui`
+    pub path: String,
+    pub behavior: DirectoryBehavior,
+}
+
+/// What a folder rule does with its files
+#[derive(
+    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Copy, PartialEq, Debug,
+)]
+pub enum DirectoryBehavior {
+    /// Textures become material folders
+    #[serde(rename = "convert")]
+    Convert,
+    /// Files are copied as they are
+    #[serde(rename = "keep")]
+    Keep,
+    /// Files are left out of the project
+    #[serde(rename = "ignore")]
+    Ignore,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+impl Default for ImportRules {
+    // Folders of non-block textures the importer never converted
+    fn default() -> Self {
+        let exclude = [
+            "colormap",
+            "effect",
+            "environment",
+            "font",
+            "gui",
+            "map",
+            "misc",
+            "mob_effect",
+            "models",
+        ]
+        .iter()
+        .map(|dir| format!("**/textures/{}/**", dir))
+        .collect();
+
+        let suffixes = [("_n", "normal"), ("_s", "specular")]
+            .iter()
+            .map(|(suffix, channel)| (suffix.to_string(), channel.to_string()))
+            .collect();
+
+        ImportRules {
+            include: Vec::new(),
+            exclude,
+            suffixes,
+            directories: Vec::new(),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone)]
+/// A pack published from the project
 pub struct Profile {
     pub edition: GameEdition,
+    /// Pack format number written to pack.mcmeta
     pub format: i8,
     pub name: String,
     pub description: Option<String>,
+    /// Block textures are resized to this many pixels wide
+    #[schemars(range(min = 1))]
     pub block_texture_size: Option<i16>,
+    /// Item textures are resized to this many pixels wide
+    #[schemars(range(min = 1))]
     pub item_texture_size: Option<i16>,
+    /// Multiplies the size of textures outside block and item folders
+    #[schemars(range(min = 1))]
     pub texture_scale: Option<i8>,
+    /// Stretches height maps to use the full 0 to 255 range
     pub auto_level_height: Option<bool>,
+    /// Darkens the color texture by the occlusion map
     pub bake_occlusion_texture: Option<bool>,
+    // Java only, Bedrock packs always use texture sets
+    /// Packed layout for Java packs
+    pub output_format: Option<OutputFormat>,
+}
+
+/// Which packed layout a Java profile is published in
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    Clone,
+    Copy,
+    PartialEq,
+    Debug,
+    Default,
+)]
+pub enum OutputFormat {
+    #[default]
+    #[serde(rename = "labpbr-1.3")]
+    LabPbr13,
+    #[serde(rename = "oldpbr")]
+    OldPbr,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Copy, PartialEq)]
 pub enum GameEdition {
     Java,
     Bedrock,
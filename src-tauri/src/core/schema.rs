@@ -0,0 +1,91 @@
+// JSON Schema for mat.yml and project.yml. The files are written into every
+// project so YAML language servers (e.g. the VS Code YAML extension) can
+// complete and check hand-edited files.
+
+use schemars::{schema::RootSchema, schema_for};
+use serde_json::{json, Map, Value};
+use std::{fs, io, path::Path};
+
+use super::{
+    error::{CommandResult, Error},
+    interface::structs::MatYml,
+    project::structs::ProjectYml,
+};
+
+pub const SCHEMA_DIR: &str = ".pixeltoolkit/schema";
+pub const MAT_YML_SCHEMA: &str = "mat.schema.json";
+pub const PROJECT_YML_SCHEMA: &str = "project.schema.json";
+
+// Every file name build_file_tree accepts for a material, and templates
+const MAT_YML_GLOBS: [&str; 5] = [
+    "**/mat.yml",
+    "**/mat.yaml",
+    "**/material.yml",
+    "**/material.yaml",
+    "**/_templates/*.yml",
+];
+
+pub fn mat_yml_schema() -> RootSchema {
+    schema_for!(MatYml)
+}
+
+pub fn project_yml_schema() -> RootSchema {
+    schema_for!(ProjectYml)
+}
+
+// Writes both schemas into `.pixeltoolkit/schema/`, leaving files that are
+// already up to date alone
+pub fn write_schemas(project_dir: &Path) -> CommandResult<()> {
+    let schema_dir = project_dir.join(SCHEMA_DIR);
+    fs::create_dir_all(&schema_dir).map_err(|e| Error::io(&schema_dir, e))?;
+
+    for (file_name, schema) in [
+        (MAT_YML_SCHEMA, mat_yml_schema()),
+        (PROJECT_YML_SCHEMA, project_yml_schema()),
+    ] {
+        let path = schema_dir.join(file_name);
+        let contents = serde_json::to_string_pretty(&schema).map_err(|e| Error::other(&path, e))?;
+
+        if fs::read_to_string(&path).is_ok_and(|existing| existing == contents) {
+            continue;
+        }
+        fs::write(&path, contents).map_err(|e| Error::io(&path, e))?;
+    }
+
+    associate_schemas(project_dir)
+}
+
+// Points VS Code's YAML extension at the schemas. An existing settings file is
+// only changed when it's plain JSON without a `yaml.schemas` of its own.
+fn associate_schemas(project_dir: &Path) -> CommandResult<()> {
+    let settings_dir = project_dir.join(".vscode");
+    let settings_path = settings_dir.join("settings.json");
+
+    let mut settings = match fs::read_to_string(&settings_path) {
+        Ok(contents) => match serde_json::from_str(&contents) {
+            Ok(Value::Object(settings)) => settings,
+            _ => return Ok(()),
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+        Err(e) => return Err(Error::io(&settings_path, e)),
+    };
+    if settings.contains_key("yaml.schemas") {
+        return Ok(());
+    }
+
+    let mut schemas = Map::new();
+    schemas.insert(
+        format!("./{}/{}", SCHEMA_DIR, MAT_YML_SCHEMA),
+        json!(MAT_YML_GLOBS),
+    );
+    schemas.insert(
+        format!("./{}/{}", SCHEMA_DIR, PROJECT_YML_SCHEMA),
+        json!(["project.yml"]),
+    );
+    settings.insert("yaml.schemas".to_string(), Value::Object(schemas));
+
+    fs::create_dir_all(&settings_dir).map_err(|e| Error::io(&settings_dir, e))?;
+    let contents =
+        serde_json::to_string_pretty(&settings).map_err(|e| Error::other(&settings_path, e))?;
+    fs::write(&settings_path, contents).map_err(|e| Error::io(&settings_path, e))
+}
@@ -0,0 +1,249 @@
+// Reading and writing the yml files more than one command touches
+// (projects.yml, project.yml, mat.yml).
+//
+// Writes go to a temp file in the same folder which is then renamed over the
+// target, so a crash never leaves a half-written file. The previous version is
+// kept next to it as `.<name>.bak`. Only projects.yml, which nobody edits by
+// hand, is restored from it when broken, a bad project.yml or mat.yml is
+// reported so the user can fix their edit.
+// Read-modify-write cycles lock `.<name>.lock` so two commands, or two
+// processes, can't drop each other's changes. Both sidecars start with a dot
+// so publishing skips them.
+
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tempfile::NamedTempFile;
+
+use super::error::{CommandResult, Error};
+
+// How long to wait for another command to let go of a file
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY: Duration = Duration::from_millis(20);
+
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}", name, suffix))
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    sidecar(path, "bak")
+}
+
+// Held for a whole read-modify-write. This is an OS advisory lock on the
+// lock file, so it's let go of when the process dies and the file itself is
+// never removed, removing it would let a second holder in.
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    pub fn acquire(target: &Path) -> CommandResult<Self> {
+        let path = sidecar(target, "lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| Error::io(&path, e))?;
+        let start = Instant::now();
+
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(FileLock { _file: file }),
+                Err(TryLockError::WouldBlock) => {
+                    if start.elapsed() > LOCK_TIMEOUT {
+                        return Err(Error::conflict(
+                            target,
+                            "File is being written by another operation",
+                        ));
+                    }
+                    thread::sleep(LOCK_RETRY);
+                }
+                Err(TryLockError::Error(e)) => return Err(Error::io(&path, e)),
+            }
+        }
+    }
+}
+
+fn replace_file(path: &Path, contents: &[u8]) -> CommandResult<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let mut temp = NamedTempFile::new_in(dir).map_err(|e| Error::io(dir, e))?;
+    temp.write_all(contents)
+        .and_then(|_| temp.as_file().sync_all())
+        .map_err(|e| Error::io(temp.path(), e))?;
+    temp.persist(path).map_err(|e| Error::io(path, e.error))?;
+
+    Ok(())
+}
+
+// Replaces `path` in one step, keeping what was there as the backup
+pub fn write_atomic(path: &Path, contents: &[u8]) -> CommandResult<()> {
+    if path.is_file() {
+        let backup = backup_path(path);
+        fs::copy(path, &backup).map_err(|e| Error::io(&backup, e))?;
+    }
+
+    replace_file(path, contents)
+}
+
+pub fn write_yaml<T: Serialize>(path: &Path, value: &T) -> CommandResult<()> {
+    let contents = serde_yaml::to_string(value).map_err(|e| Error::parse(path, e))?;
+    write_atomic(path, contents.as_bytes())
+}
+
+pub fn read_with<T>(path: &Path, parse: impl Fn(&str) -> CommandResult<T>) -> CommandResult<T> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+    parse(&contents)
+}
+
+pub fn read_yaml_or_recover<T: DeserializeOwned>(path: &Path) -> CommandResult<T> {
+    read_or_recover(path, |contents| {
+        serde_yaml::from_str(contents).map_err(|e| Error::parse(path, e))
+    })
+}
+
+// Reads `path` with `parse`, falling back to its backup when the file can't
+// be read or isn't valid yml. A recovered file is put back in place and the
+// broken one is kept as `.<name>.corrupt` for inspection. Files that parse but
+// hold a bad value are reported, not replaced.
+fn read_or_recover<T>(path: &Path, parse: impl Fn(&str) -> CommandResult<T>) -> CommandResult<T> {
+    let error = match fs::read_to_string(path).map_err(|e| Error::io(path, e)) {
+        Ok(contents) => match parse(&contents) {
+            Ok(value) => return Ok(value),
+            Err(e @ Error::Parse { .. }) => e,
+            Err(e) => return Err(e),
+        },
+        // Nothing to recover, the file was never written
+        Err(e @ Error::NotFound { .. }) => return Err(e),
+        Err(e) => e,
+    };
+
+    let backup = backup_path(path);
+    let Some((contents, value)) = fs::read_to_string(&backup).ok().and_then(|contents| {
+        let value = parse(&contents).ok()?;
+        Some((contents, value))
+    }) else {
+        return Err(error);
+    };
+
+    eprintln!(
+        "Recovering {} from {}: {}",
+        path.display(),
+        backup.display(),
+        error
+    );
+    let _ = fs::copy(path, sidecar(path, "corrupt"));
+    replace_file(path, contents.as_bytes())?;
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn write_atomic_keeps_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.yml");
+
+        write_atomic(&path, b"first").unwrap();
+        assert!(!backup_path(&path).exists());
+
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(read(&path), "second");
+        assert_eq!(read(&dir.path().join(".project.yml.bak")), "first");
+
+        // No temp files are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn lock_waits_for_the_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("projects.yml");
+        let held = FileLock::acquire(&path).unwrap();
+        let acquired = Arc::new(AtomicBool::new(false));
+
+        let waiter = {
+            let (path, acquired) = (path.clone(), acquired.clone());
+            thread::spawn(move || {
+                let _lock = FileLock::acquire(&path).unwrap();
+                acquired.store(true, Ordering::SeqCst);
+            })
+        };
+
+        thread::sleep(LOCK_RETRY * 10);
+        assert!(!acquired.load(Ordering::SeqCst));
+
+        drop(held);
+        waiter.join().unwrap();
+        assert!(acquired.load(Ordering::SeqCst));
+        assert!(dir.path().join(".projects.yml.lock").exists());
+    }
+
+    #[test]
+    fn broken_yml_is_recovered_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("projects.yml");
+        write_yaml(&path, &vec!["a"]).unwrap();
+        write_yaml(&path, &vec!["a", "b"]).unwrap();
+        fs::write(&path, "- [broken").unwrap();
+
+        let value: Vec<String> = read_yaml_or_recover(&path).unwrap();
+        assert_eq!(value, vec!["a"]);
+        assert_eq!(read(&path), "- a\n");
+        assert_eq!(read(&dir.path().join(".projects.yml.corrupt")), "- [broken");
+    }
+
+    #[test]
+    fn broken_yml_without_backup_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("projects.yml");
+        fs::write(&path, "- [broken").unwrap();
+
+        let result: CommandResult<Vec<String>> = read_yaml_or_recover(&path);
+        assert!(matches!(result, Err(Error::Parse { .. })));
+        assert_eq!(read(&path), "- [broken");
+    }
+
+    #[test]
+    fn read_with_never_recovers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mat.yml");
+        write_atomic(&path, b"smooth: 1\n").unwrap();
+        write_atomic(&path, b"smooth: [broken\n").unwrap();
+
+        let result = read_with(&path, |text| {
+            serde_yaml::from_str::<serde_yaml::Value>(text).map_err(|e| Error::parse(&path, e))
+        });
+        assert!(matches!(result, Err(Error::Parse { .. })));
+        assert_eq!(read(&path), "smooth: [broken\n");
+        assert!(!dir.path().join(".mat.yml.corrupt").exists());
+
+        let missing = read_with(&dir.path().join("project.yml"), |_| Ok(()));
+        assert!(matches!(missing, Err(Error::NotFound { .. })));
+    }
+}
@@ -0,0 +1,355 @@
+// mat.yml can `extends:` a template, a yml file with the same keys. Settings
+// are merged key by key: a material's own keys win over its template's, which
+// win over the template that one extends, and so on.
+//
+// `extends: ../_templates/ore.yml` is relative to the file it's written in, a
+// bare name like `extends: ore` is looked up in the project's `_templates`
+// folder. Template folders are never published.
+
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    error::{CommandResult, Error},
+    migration::migrate_mat_yml,
+};
+
+pub const TEMPLATES_DIR: &str = "_templates";
+// Deep enough for any sensible setup, stops runaway chains early
+const MAX_DEPTH: usize = 16;
+
+pub struct Template {
+    pub path: PathBuf,
+    pub doc: Mapping,
+}
+
+fn extends_of(path: &Path, doc: &Mapping) -> CommandResult<Option<String>> {
+    match doc.get("extends") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(extends)) if !extends.trim().is_empty() => {
+            Ok(Some(extends.trim().to_string()))
+        }
+        Some(_) => Err(Error::invalid_field(
+            path,
+            "extends",
+            "Must be a template name or a path",
+        )),
+    }
+}
+
+// The closest folder holding project.yml
+pub fn project_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join("project.yml").is_file())
+        .map(Path::to_path_buf)
+}
+
+fn resolve(path: &Path, extends: &str) -> CommandResult<PathBuf> {
+    let is_name = !extends.contains(['/', '\\']) && Path::new(extends).extension().is_none();
+
+    let template_path = if is_name {
+        let root = project_root(path).ok_or_else(|| {
+            Error::invalid_field(
+                path,
+                "extends",
+                "Template names only work inside a project, use a path instead",
+            )
+        })?;
+        root.join(TEMPLATES_DIR).join(format!("{}.yml", extends))
+    } else {
+        path.parent().unwrap_or(Path::new("")).join(extends)
+    };
+
+    if !template_path.is_file() {
+        return Err(Error::invalid_field(
+            path,
+            "extends",
+            format!("Template not found: {}", template_path.display()),
+        ));
+    }
+    Ok(template_path)
+}
+
+// The templates `doc` extends, nearest first
+pub fn template_chain(path: &Path, doc: &Mapping) -> CommandResult<Vec<Template>> {
+    let mut chain: Vec<Template> = Vec::new();
+    let mut visited = vec![path.canonicalize().unwrap_or(path.to_path_buf())];
+    let mut current = path.to_path_buf();
+    let mut extends = extends_of(path, doc)?;
+
+    while let Some(name) = extends {
+        let template_path = resolve(&current, &name)?;
+        let canonical = template_path
+            .canonicalize()
+            .unwrap_or(template_path.clone());
+        if visited.contains(&canonical) {
+            return Err(Error::invalid_field(
+                &current,
+                "extends",
+                "Templates extend each other in a loop",
+            ));
+        }
+        if chain.len() >= MAX_DEPTH {
+            return Err(Error::invalid_field(
+                &current,
+                "extends",
+                format!("Templates can be nested at most {} deep", MAX_DEPTH),
+            ));
+        }
+        visited.push(canonical);
+
+        let text = fs::read_to_string(&template_path).map_err(|e| Error::io(&template_path, e))?;
+        let template_doc = migrate_mat_yml(&template_path, &text)?;
+        extends = extends_of(&template_path, &template_doc)?;
+
+        chain.push(Template {
+            path: template_path.clone(),
+            doc: template_doc,
+        });
+        current = template_path;
+    }
+
+    Ok(chain)
+}
+
+// Lays `over` on top of `base`, merging nested sections key by key
+pub fn merge(base: &mut Mapping, over: Mapping) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Mapping(base_section)), Value::Mapping(over_section)) => {
+                merge(base_section, over_section)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// Everything `doc` gets from its templates, leaving out its own keys
+pub fn inherited(path: &Path, doc: &Mapping) -> CommandResult<Mapping> {
+    let mut merged = Mapping::new();
+    for template in template_chain(path, doc)?.into_iter().rev() {
+        merge(&mut merged, template.doc);
+    }
+
+    Ok(merged)
+}
+
+pub fn apply_templates(path: &Path, doc: Mapping) -> CommandResult<Mapping> {
+    if extends_of(path, &doc)?.is_none() {
+        return Ok(doc);
+    }
+
+    let mut merged = inherited(path, &doc)?;
+    merge(&mut merged, doc);
+    Ok(merged)
+}
+
+// How a template is shown in the editor, relative to the project when it's
+// inside one
+fn display_path(template_path: &Path) -> String {
+    project_root(template_path)
+        .and_then(|root| template_path.strip_prefix(root).ok().map(Path::to_path_buf))
+        .unwrap_or(template_path.to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn section_has(doc: &Mapping, section: &str, key: &Value) -> bool {
+    doc.get(section)
+        .and_then(Value::as_mapping)
+        .is_some_and(|section| section.contains_key(key))
+}
+
+// The fields of `section` that come from a template instead of the material
+// itself, with the template each one is read from
+pub fn inherited_fields(
+    path: &Path,
+    doc: &Mapping,
+    section: &str,
+) -> CommandResult<BTreeMap<String, String>> {
+    let mut fields = BTreeMap::new();
+
+    for template in template_chain(path, doc)? {
+        let Some(template_section) = template.doc.get(section).and_then(Value::as_mapping) else {
+            continue;
+        };
+
+        for key in template_section.keys() {
+            let Some(name) = key.as_str() else {
+                continue;
+            };
+            if !section_has(doc, section, key) && !fields.contains_key(name) {
+                fields.insert(name.to_string(), display_path(&template.path));
+            }
+        }
+    }
+
+    Ok(fields)
+}
+
+// Whether setting `section.key` to `value` would only repeat what the
+// material already inherits, so the key can stay unset and keep following
+// its template
+pub fn is_inherited_value(
+    inherited: &Mapping,
+    doc: &Mapping,
+    section: &str,
+    key: &str,
+    value: &Value,
+) -> bool {
+    if section_has(doc, section, &Value::from(key)) {
+        return false;
+    }
+
+    let Some(current) = inherited
+        .get(section)
+        .and_then(Value::as_mapping)
+        .and_then(|section| section.get(key))
+    else {
+        return false;
+    };
+
+    match (current.as_f64(), value.as_f64()) {
+        (Some(a), Some(b)) => (a - b).abs() < 1e-6,
+        _ => current == value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A project with `files` written relative to its root
+    fn project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("project.yml"), "name: Test\n").unwrap();
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn load(path: &Path) -> CommandResult<Mapping> {
+        let text = fs::read_to_string(path).unwrap();
+        migrate_mat_yml(path, &text)
+    }
+
+    fn get<'a>(doc: &'a Mapping, section: &str, key: &str) -> Option<&'a Value> {
+        doc.get(section)?.get(key)
+    }
+
+    #[test]
+    fn two_level_chain_with_local_overrides() {
+        let dir = project(&[
+            (
+                "_templates/base.yml",
+                "smooth:\n  value: 10\n  scale: 2\nrough:\n  value: 50\n",
+            ),
+            (
+                "_templates/ore.yml",
+                "extends: base\nsmooth:\n  value: 20\nemissive:\n  value: 5\n",
+            ),
+            (
+                "stone/mat.yml",
+                "extends: ore\nsmooth:\n  scale: 3\nrough:\n  value: 60\n",
+            ),
+        ]);
+        let path = dir.path().join("stone/mat.yml");
+        let local = load(&path).unwrap();
+        let merged = apply_templates(&path, local.clone()).unwrap();
+
+        assert_eq!(get(&merged, "smooth", "value"), Some(&Value::from(20)));
+        assert_eq!(get(&merged, "smooth", "scale"), Some(&Value::from(3)));
+        assert_eq!(get(&merged, "rough", "value"), Some(&Value::from(60)));
+        assert_eq!(get(&merged, "emissive", "value"), Some(&Value::from(5)));
+
+        let smooth = inherited_fields(&path, &local, "smooth").unwrap();
+        assert_eq!(
+            smooth.into_iter().collect::<Vec<_>>(),
+            [("value".to_string(), "_templates/ore.yml".to_string())]
+        );
+        assert!(inherited_fields(&path, &local, "rough").unwrap().is_empty());
+        assert_eq!(
+            inherited_fields(&path, &local, "emissive").unwrap()["value"],
+            "_templates/ore.yml"
+        );
+    }
+
+    #[test]
+    fn bare_names_resolve_under_templates() {
+        let dir = project(&[
+            ("_templates/ore.yml", "smooth:\n  value: 20\n"),
+            ("blocks/iron/mat.yml", "extends: ore\n"),
+            ("blocks/gold/mat.yml", "extends: ../../_templates/ore.yml\n"),
+        ]);
+
+        for material in ["blocks/iron/mat.yml", "blocks/gold/mat.yml"] {
+            let path = dir.path().join(material);
+            let chain = template_chain(&path, &load(&path).unwrap()).unwrap();
+            assert_eq!(chain.len(), 1);
+            assert_eq!(
+                chain[0].path.canonicalize().unwrap(),
+                dir.path()
+                    .join("_templates/ore.yml")
+                    .canonicalize()
+                    .unwrap()
+            );
+        }
+
+        let path = dir.path().join("blocks/iron/mat.yml");
+        fs::write(&path, "extends: missing\n").unwrap();
+        assert!(matches!(
+            template_chain(&path, &load(&path).unwrap()),
+            Err(Error::InvalidField { field, .. }) if field == "extends"
+        ));
+    }
+
+    #[test]
+    fn extends_loops_are_errors() {
+        let dir = project(&[
+            ("stone/mat.yml", "extends: mat.yml\n"),
+            ("_templates/a.yml", "extends: b\n"),
+            ("_templates/b.yml", "extends: a\n"),
+            ("ore/mat.yml", "extends: a\n"),
+        ]);
+
+        for material in ["stone/mat.yml", "ore/mat.yml"] {
+            let path = dir.path().join(material);
+            let result = apply_templates(&path, load(&path).unwrap());
+            assert!(
+                matches!(&result, Err(Error::InvalidField { field, message, .. })
+                    if field == "extends" && message.contains("loop")),
+                "{}: {:?}",
+                material,
+                result.err()
+            );
+        }
+    }
+
+    #[test]
+    fn inherited_values_compare_as_numbers() {
+        let dir = project(&[
+            ("_templates/ore.yml", "smooth:\n  value: 20\n"),
+            ("stone/mat.yml", "extends: ore\nrough:\n  value: 3\n"),
+        ]);
+        let path = dir.path().join("stone/mat.yml");
+        let local = load(&path).unwrap();
+        let from_templates = inherited(&path, &local).unwrap();
+
+        let is_inherited = |section, key, value: f64| {
+            is_inherited_value(&from_templates, &local, section, key, &Value::from(value))
+        };
+        assert!(is_inherited("smooth", "value", 20.0));
+        assert!(!is_inherited("smooth", "value", 21.0));
+        assert!(!is_inherited("smooth", "scale", 1.0));
+        assert!(!is_inherited("rough", "value", 3.0));
+    }
+}
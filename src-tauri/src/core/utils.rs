@@ -4,20 +4,26 @@ use std::{
     fs,
     path::{Path, PathBuf},
 };
-use tauri::{Emitter, Manager};
 
-use super::home::{get_projects_vec, Project};
+use super::{
+    error::{CommandResult, Error},
+    events::EventSink,
+    home::{get_projects_vec, Project},
+};
 
-#[tauri::command]
-pub fn get_config_dir(app: &tauri::AppHandle) -> String {
-    let config_dir = app.path().app_data_dir().unwrap_or(PathBuf::new());
+pub fn get_config_dir(sink: &dyn EventSink) -> String {
+    let config_dir = sink.config_dir().unwrap_or_default();
     let config_dir_str = config_dir.to_string_lossy().into_owned();
 
     config_dir_str
 }
 
 #[tauri::command]
-pub fn show_in_folder(mut path: String, is_id: Option<bool>, app: tauri::AppHandle) {
+pub fn show_in_folder(
+    mut path: String,
+    is_id: Option<bool>,
+    app: tauri::AppHandle,
+) -> CommandResult<()> {
     let platform = tauri_plugin_os::platform();
 
     if is_id.unwrap_or(true) {
@@ -28,28 +34,34 @@ pub fn show_in_folder(mut path: String, is_id: Option<bool>, app: tauri::AppHand
         }
     }
 
-    if platform == "windows" {
+    let result = if platform == "windows" {
         Command::new("explorer")
             .args(["/select,", &path]) // The comma after select is not a typo
             .spawn()
-            .unwrap();
     } else if platform == "linux" {
-        let new_path = match fs::metadata(&path).unwrap().is_dir() {
-            true => path,
+        let metadata = fs::metadata(&path).map_err(|e| Error::io(&path, e))?;
+        let new_path = match metadata.is_dir() {
+            true => PathBuf::from(&path),
             false => {
-                let mut path2 = PathBuf::from(path);
+                let mut path2 = PathBuf::from(&path);
                 path2.pop();
-                path2.into_os_string().into_string().unwrap()
+                path2
             }
         };
-        Command::new("xdg-open").arg(&new_path).spawn().unwrap();
+        Command::new("xdg-open").arg(&new_path).spawn()
     } else if platform == "macos" {
-        Command::new("open").args(["-R", &path]).spawn().unwrap();
-    }
+        Command::new("open").args(["-R", &path]).spawn()
+    } else {
+        return Ok(());
+    };
+
+    result
+        .map(|_| ())
+        .map_err(|e| Error::other(&path, format!("Failed to open file manager: {}", e)))
 }
 
-pub fn simple_toast(message: String, app: tauri::AppHandle) {
-    app.emit("simple-toast", message).unwrap();
+pub fn simple_toast(message: String, sink: &dyn EventSink) {
+    sink.toast(&message);
 }
 
 pub fn try_create_directory(base_path: &Path, subdirs: &[&str]) {
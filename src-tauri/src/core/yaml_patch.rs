@@ -0,0 +1,443 @@
+// Editing single values in block-style yml so that saving one setting leaves
+// every other key, comment and blank line exactly as it was.
+//
+// Anything that can't be edited in place (flow mappings, block scalars,
+// anchors, tabs, ...) falls back to editing the parsed document, which keeps
+// unknown keys but drops comments. Documents with comments are refused
+// instead, so the user can be told to make that edit by hand.
+
+use serde_yaml::{Mapping, Value};
+
+// Used for new sections when the document has no nested keys to copy from
+const DEFAULT_STEP: usize = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PatchError {
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    // A key on the way to the value holds something other than a section,
+    // e.g. `normal: 5` when setting `normal.curve_x`
+    #[error("Must be a section, found {found}")]
+    NotASection { key: String, found: String },
+    // Setting `key` would mean rewriting the document and losing its comments
+    #[error("Can't set {key} without losing the comments in this file, edit it by hand")]
+    WouldDropComments { key: String },
+}
+
+// Sets the value at `path`, e.g. `["normal", "curve_x"]`, to `scalar`, which
+// is written as is and must be a valid yml scalar
+pub fn set_value(doc: &str, path: &[&str], scalar: &str) -> Result<String, PatchError> {
+    if let Some(patched) = set_in_place(doc, path, scalar) {
+        if reads_back(&patched, path, scalar) {
+            return Ok(patched);
+        }
+    }
+
+    if has_comments(doc) {
+        return Err(PatchError::WouldDropComments {
+            key: path.join("."),
+        });
+    }
+    set_parsed(doc, path, scalar)
+}
+
+// Like set_value for a top-level key, but a missing key becomes the first one
+// in the document, below any leading comments and `---` marker
+pub fn set_leading_value(doc: &str, key: &str, scalar: &str) -> Result<String, PatchError> {
+    let mut lines: Vec<&str> = doc.split('\n').collect();
+    let missing = !lines
+        .iter()
+        .any(|line| parse_key_line(line, 0, key).is_some());
+    let first = lines.iter().position(|line| is_content(line));
+
+    if let (true, Some(at)) = (missing, first) {
+        if indent_of(lines[at]) == 0 {
+            let entry = format!("{}: {}", key, scalar);
+            lines.insert(at, &entry);
+            let patched = lines.join("\n");
+            if reads_back(&patched, &[key], scalar) {
+                return Ok(patched);
+            }
+        }
+    }
+
+    set_value(doc, &[key], scalar)
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+// Blank lines, comments and document markers don't open or close sections
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !(trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" || trimmed == "...")
+}
+
+// Where a trailing ` # comment` starts, ignoring `#` inside quotes
+fn find_comment(tail: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut after_space = true;
+
+    for (i, c) in tail.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') if after_space => quote = Some(c),
+            (None, '#') if after_space => return Some(i),
+            _ => (),
+        }
+        after_space = c == ' ' || c == '\t';
+    }
+    None
+}
+
+fn has_comments(doc: &str) -> bool {
+    doc.lines().any(|line| find_comment(line).is_some())
+}
+
+// `key: value  # comment`, split at the colon and at the end of the value
+struct KeyLine {
+    after_colon: usize,
+    value_end: usize,
+    value: String,
+}
+
+fn parse_key_line(line: &str, indent: usize, key: &str) -> Option<KeyLine> {
+    let line = line.trim_end_matches('\r');
+    let rest = line.get(indent..)?;
+
+    let key_len = [
+        key.to_string(),
+        format!("\"{}\"", key),
+        format!("'{}'", key),
+    ]
+    .into_iter()
+    .find(|k| rest.starts_with(k.as_str()) && rest[k.len()..].starts_with(':'))?
+    .len();
+
+    let after_colon = indent + key_len + 1;
+    let tail = &line[after_colon..];
+    // `key:value` is a plain scalar, not a key
+    if !(tail.is_empty() || tail.starts_with(' ') || tail.starts_with('\t')) {
+        return None;
+    }
+
+    let comment = find_comment(tail).map_or(line.len(), |i| after_colon + i);
+    let value_end = after_colon + line[after_colon..comment].trim_end().len();
+
+    Some(KeyLine {
+        after_colon,
+        value_end,
+        value: line[after_colon..value_end].trim().to_string(),
+    })
+}
+
+// The first line after `idx` that belongs to a sibling or a parent
+fn section_end(lines: &[String], idx: usize, indent: usize, end: usize) -> usize {
+    (idx + 1..end)
+        .find(|&i| {
+            let line = &lines[i];
+            let line_indent = indent_of(line);
+            is_content(line)
+                && (line_indent < indent
+                    || (line_indent == indent && !line.trim_start().starts_with('-')))
+        })
+        .unwrap_or(end)
+}
+
+fn set_in_place(doc: &str, path: &[&str], scalar: &str) -> Option<String> {
+    let mut lines: Vec<String> = doc.split('\n').map(str::to_string).collect();
+    if lines.iter().any(|line| line.starts_with('\t')) {
+        return None;
+    }
+
+    let step = lines
+        .iter()
+        .filter(|line| is_content(line))
+        .map(|line| indent_of(line))
+        .filter(|&indent| indent > 0)
+        .min()
+        .unwrap_or(DEFAULT_STEP);
+    let mut indent = lines
+        .iter()
+        .find(|line| is_content(line))
+        .map_or(0, |line| indent_of(line));
+    let (mut start, mut end) = (0, lines.len());
+
+    for (depth, key) in path.iter().enumerate() {
+        let found = (start..end).find_map(|i| {
+            let line = &lines[i];
+            if !is_content(line) || indent_of(line) != indent {
+                return None;
+            }
+            parse_key_line(line, indent, key).map(|key_line| (i, key_line))
+        });
+        let Some((idx, key_line)) = found else {
+            insert_missing(&mut lines, start, end, indent, step, &path[depth..], scalar);
+            return Some(lines.join("\n"));
+        };
+
+        let section_end = section_end(&lines, idx, indent, end);
+        let child_indent = (idx + 1..section_end)
+            .find(|&i| is_content(&lines[i]))
+            .map(|i| indent_of(&lines[i]));
+        let line = &lines[idx];
+
+        if depth + 1 == path.len() {
+            // Only plain one-line scalars are replaced
+            if key_line.value.is_empty()
+                || child_indent.is_some()
+                || key_line
+                    .value
+                    .starts_with(['|', '>', '&', '*', '!', '[', '{'])
+            {
+                return None;
+            }
+            lines[idx] = format!(
+                "{} {}{}",
+                &line[..key_line.after_colon],
+                scalar,
+                &line[key_line.value_end..]
+            );
+            return Some(lines.join("\n"));
+        }
+
+        match (key_line.value.as_str(), child_indent) {
+            ("", Some(child_indent)) if child_indent > indent => indent = child_indent,
+            ("" | "~" | "null" | "{}", None) => {
+                lines[idx] = format!(
+                    "{}{}",
+                    &line[..key_line.after_colon],
+                    &line[key_line.value_end..]
+                );
+                indent += step;
+            }
+            _ => return None,
+        }
+        (start, end) = (idx + 1, section_end);
+    }
+
+    None
+}
+
+// Adds `keys` after the last line of the section, before any comments or
+// blank lines that lead into the next one
+fn insert_missing(
+    lines: &mut Vec<String>,
+    start: usize,
+    end: usize,
+    indent: usize,
+    step: usize,
+    keys: &[&str],
+    scalar: &str,
+) {
+    // A document that's only `---` gets its keys after the marker
+    let at = (start..end)
+        .rev()
+        .find(|&i| is_content(&lines[i]) || lines[i].trim() == "---")
+        .map_or(start, |i| i + 1);
+
+    let new_lines = keys.iter().enumerate().map(|(depth, key)| {
+        let pad = " ".repeat(indent + depth * step);
+        if depth + 1 == keys.len() {
+            format!("{}{}: {}", pad, key, scalar)
+        } else {
+            format!("{}{}:", pad, key)
+        }
+    });
+    lines.splice(at..at, new_lines.collect::<Vec<_>>());
+}
+
+// Guards the line editing: the patched document must still parse and hold
+// the new value where it was meant to go
+fn reads_back(doc: &str, path: &[&str], scalar: &str) -> bool {
+    let (Ok(root), Ok(expected)) = (
+        serde_yaml::from_str::<Value>(doc),
+        serde_yaml::from_str::<Value>(scalar),
+    ) else {
+        return false;
+    };
+
+    path.iter().try_fold(&root, |node, key| node.get(*key)) == Some(&expected)
+}
+
+fn set_parsed(doc: &str, path: &[&str], scalar: &str) -> Result<String, PatchError> {
+    let mut root: Value = serde_yaml::from_str(doc)?;
+    let value: Value = serde_yaml::from_str(scalar)?;
+
+    let mut node = &mut root;
+    for (depth, key) in path.iter().enumerate() {
+        // An empty document or `key:` / `key: ~` is an empty section
+        if node.is_null() {
+            *node = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(map) = node else {
+            return Err(PatchError::NotASection {
+                key: match depth {
+                    0 => ".".to_string(),
+                    _ => path[..depth].join("."),
+                },
+                found: serde_yaml::to_string(node)?.trim().to_string(),
+            });
+        };
+        node = map.entry(Value::from(*key)).or_insert(Value::Null);
+    }
+    *node = value;
+
+    Ok(serde_yaml::to_string(&root)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(doc: &str, path: &[&str], scalar: &str) -> String {
+        set_value(doc, path, scalar).unwrap()
+    }
+
+    #[test]
+    fn replaces_value_keeping_comment() {
+        let doc = "# Stone\nnormal:\n  curve_x: 0.5  # flatter\n  curve_y: 1\n\ncolor: {}\n";
+        assert_eq!(
+            set(doc, &["normal", "curve_x"], "2"),
+            "# Stone\nnormal:\n  curve_x: 2  # flatter\n  curve_y: 1\n\ncolor: {}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_hash_inside_quotes() {
+        let doc = "color:\n  tint: '#ff0000' # red\n";
+        assert_eq!(
+            set(doc, &["color", "tint"], "'#00ff00'"),
+            "color:\n  tint: '#00ff00' # red\n"
+        );
+    }
+
+    #[test]
+    fn inserts_missing_key_at_end_of_section() {
+        let doc = "normal:\n  curve_x: 1\n\n# Next\nheight:\n  scale: 2\n";
+        assert_eq!(
+            set(doc, &["normal", "curve_y"], "3"),
+            "normal:\n  curve_x: 1\n  curve_y: 3\n\n# Next\nheight:\n  scale: 2\n"
+        );
+    }
+
+    #[test]
+    fn inserts_missing_section() {
+        let doc = "# Stone\nnormal:\n  curve_x: 1\n";
+        assert_eq!(
+            set(doc, &["height", "scale"], "2"),
+            "# Stone\nnormal:\n  curve_x: 1\nheight:\n  scale: 2\n"
+        );
+        assert_eq!(set("", &["height", "scale"], "2"), "height:\n  scale: 2\n");
+    }
+
+    #[test]
+    fn inserts_after_document_marker() {
+        assert_eq!(
+            set(
+                "# Stone\n---\nnormal:\n  curve_x: 1\n",
+                &["schema_version"],
+                "1"
+            ),
+            "# Stone\n---\nnormal:\n  curve_x: 1\nschema_version: 1\n"
+        );
+        assert_eq!(
+            set("---\n", &["schema_version"], "1"),
+            "---\nschema_version: 1\n"
+        );
+    }
+
+    #[test]
+    fn leading_value_goes_before_the_first_key() {
+        assert_eq!(
+            set_leading_value("# Stone\n---\nrough:\n  value: 3\n", "schema_version", "1").unwrap(),
+            "# Stone\n---\nschema_version: 1\nrough:\n  value: 3\n"
+        );
+        assert_eq!(
+            set_leading_value(
+                "rough:\n  value: 3\nschema_version: 0\n",
+                "schema_version",
+                "1"
+            )
+            .unwrap(),
+            "rough:\n  value: 3\nschema_version: 1\n"
+        );
+        assert_eq!(
+            set_leading_value("---\n", "schema_version", "1").unwrap(),
+            "---\nschema_version: 1\n"
+        );
+    }
+
+    #[test]
+    fn detects_four_space_indent() {
+        let doc = "normal:\n    curve_x: 1\n";
+        assert_eq!(
+            set(doc, &["height", "scale"], "2"),
+            "normal:\n    curve_x: 1\nheight:\n    scale: 2\n"
+        );
+        assert_eq!(
+            set(doc, &["normal", "curve_y"], "2"),
+            "normal:\n    curve_x: 1\n    curve_y: 2\n"
+        );
+    }
+
+    #[test]
+    fn fills_null_sections() {
+        for (empty, kept) in [
+            ("normal:", "normal:"),
+            ("normal: ~", "normal:"),
+            ("normal: null  # later", "normal:  # later"),
+            ("normal: {}", "normal:"),
+        ] {
+            let doc = format!("{}\nheight:\n  scale: 2\n", empty);
+            assert_eq!(
+                set(&doc, &["normal", "curve_x"], "1"),
+                format!("{}\n  curve_x: 1\nheight:\n  scale: 2\n", kept)
+            );
+        }
+    }
+
+    #[test]
+    fn falls_back_for_flow_mappings() {
+        let doc = "normal: {curve_x: 1, curve_y: 2}\nkeep: me\n";
+        let patched = set(doc, &["normal", "curve_x"], "5");
+        let value: Value = serde_yaml::from_str(&patched).unwrap();
+
+        assert_eq!(value["normal"]["curve_x"], Value::from(5));
+        assert_eq!(value["normal"]["curve_y"], Value::from(2));
+        assert_eq!(value["keep"], Value::from("me"));
+    }
+
+    #[test]
+    fn keeps_comments_or_refuses() {
+        for doc in [
+            "# Stone\nnormal: {curve_x: 1}\n",
+            "normal: {curve_x: 1}  # flat\n",
+        ] {
+            match set_value(doc, &["normal", "curve_x"], "5") {
+                Err(PatchError::WouldDropComments { key }) => assert_eq!(key, "normal.curve_x"),
+                result => panic!("expected WouldDropComments, got {:?}", result),
+            }
+        }
+
+        // A `#` inside a value isn't a comment
+        let doc = "color: {tint: '#ff0000'}\nurl: a#b\n";
+        let patched = set(doc, &["color", "tint"], "'#00ff00'");
+        let value: Value = serde_yaml::from_str(&patched).unwrap();
+        assert_eq!(value["color"]["tint"], Value::from("#00ff00"));
+    }
+
+    #[test]
+    fn refuses_to_replace_a_value_with_a_section() {
+        let doc = "normal: 5\n";
+        match set_value(doc, &["normal", "curve_x"], "1") {
+            Err(PatchError::NotASection { key, found }) => {
+                assert_eq!(key, "normal");
+                assert_eq!(found, "5");
+            }
+            result => panic!("expected NotASection, got {:?}", result),
+        }
+    }
+}
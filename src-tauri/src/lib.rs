@@ -1,4 +1,5 @@
-mod core;
+pub mod cli;
+pub mod core;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -25,13 +26,28 @@ pub fn run() {
             core::home::remove_project,
             core::home::create_project,
             core::home::create_project_existing,
+            // import
+            core::import::cancel_import,
             // project
             core::project::get_dirs,
+            core::project::profiles::list_profiles,
+            core::project::profiles::create_profile,
+            core::project::profiles::update_profile,
+            core::project::profiles::duplicate_profile,
+            core::project::profiles::delete_profile,
             // interface
             core::interface::select_texture,
             core::interface::select_texture_file,
             core::interface::update_defaults_grayscale,
             core::interface::update_normals,
+            core::interface::get_inherited_fields,
+            core::interface::update_hcm,
+            core::metals::list_metals,
+            core::interface::update_height,
+            core::interface::update_color,
+            // export
+            core::export::export_labpbr_textures,
+            core::export::publish::publish_profile,
         ])
         .setup(|_app| {
             #[cfg(desktop)]
@@ -11,7 +11,7 @@ import React, { useCallback, useEffect, useState } from "react";
 import { FixedSizeList as List } from "react-window";
 import { twMerge } from "tailwind-merge";
 import { Button } from "./ui/button";
-import { buildPath } from "@/lib/utils";
+import { buildPath, errorMessage } from "@/lib/utils";
 import { toast } from "sonner";
 
 type FlattenedEntry = {
@@ -99,7 +99,7 @@ export const FileTreeFolder: React.FC<{
         setSelectedMaterial(String(res));
       }
     } catch (err) {
-      toast("Failed to select texture: " + String(err));
+      toast("Failed to select texture: " + errorMessage(err));
       console.error("Failed to select texture: ", String(err));
     }
   }
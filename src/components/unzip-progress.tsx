@@ -1,56 +1,96 @@
-import React, { useEffect, useState, useCallback, useMemo } from "react";
+import React, {
+  useEffect,
+  useState,
+  useCallback,
+  useMemo,
+  useRef,
+} from "react";
 import {
   Dialog,
   DialogContent,
   DialogHeader,
   DialogTitle,
 } from "@/components/ui/dialog";
+import { Button } from "@/components/ui/button";
+import { Progress } from "@/components/ui/progress";
+import { ImportProgress, ImportStage, ImportSummary } from "@/types/home";
+import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { FixedSizeList as List } from "react-window";
 
+const STAGE_LABELS: Record<ImportStage, string> = {
+  extracting: "Extracting files",
+  converting: "Converting textures",
+  "texture-sets": "Converting texture sets",
+  finishing: "Writing import report",
+};
+
+// Share of the current stage that is done, null when it can't be known
+function stagePercent(progress: ImportProgress): number | null {
+  switch (progress.stage) {
+    case "extracting":
+      return progress.total_entries
+        ? (progress.extracted / progress.total_entries) * 100
+        : null;
+    case "converting":
+      return progress.extracted
+        ? (progress.processed / progress.extracted) * 100
+        : null;
+    case "finishing":
+      return 100;
+    default:
+      return null;
+  }
+}
+
+function formatDuration(ms: number): string {
+  const seconds = Math.round(ms / 1000);
+  if (seconds < 60) return `${seconds}s`;
+  return `${Math.floor(seconds / 60)}m ${seconds % 60}s`;
+}
+
 export function UnzipProgress() {
   const [open, setOpen] = useState(false);
+  const [progress, setProgress] = useState<ImportProgress | null>(null);
+  // When the current stage started, for the ETA
+  const stageStart = useRef<{ stage: ImportStage; elapsedMs: number }>();
   const [curr, setCurr] = useState<string[]>([]);
+  const [cancelling, setCancelling] = useState(false);
 
-  useEffect(() => {
-    const unzipStartedUnlisten = listen<boolean>("unzip-started", (e) =>
-      setOpen(e.payload),
-    );
-    return () => {
-      unzipStartedUnlisten.then((unlisten) => unlisten());
-    };
-  }, []);
+  async function cancelImport() {
+    setCancelling(true);
+    await invoke("cancel_import", { projectId: progress?.project_id });
+  }
 
   useEffect(() => {
-    let updateTimeout: NodeJS.Timeout | null;
-    const updates: string[] = [];
-
-    async function init() {
-      const unlisten = await listen<string>("unzip-progress", (e) => {
-        updates.push(e.payload);
-
-        if (!updateTimeout) {
-          updateTimeout = setTimeout(() => {
-            setCurr((prev) => [...updates, ...prev].slice(0, 1000));
-            updates.length = 0;
-            updateTimeout = null;
-          }, 10);
-        }
+    const progressUnlisten = listen<ImportProgress>("import-progress", (e) => {
+      if (stageStart.current?.stage !== e.payload.stage) {
+        stageStart.current = {
+          stage: e.payload.stage,
+          elapsedMs: e.payload.elapsed_ms,
+        };
+      }
+      setOpen(true);
+      setProgress(e.payload);
 
-        if (!e.payload) {
-          setCurr([]);
-        }
-      });
+      const current = e.payload.current;
+      if (current) {
+        setCurr((prev) => [current, ...prev].slice(0, 1000));
+      }
+    });
 
-      return () => {
-        unlisten();
-        if (updateTimeout) {
-          clearTimeout(updateTimeout);
-        }
-      };
-    }
+    const finishedUnlisten = listen<ImportSummary>("import-finished", () => {
+      setOpen(false);
+      setProgress(null);
+      stageStart.current = undefined;
+      setCurr([]);
+      setCancelling(false);
+    });
 
-    init();
+    return () => {
+      progressUnlisten.then((unlisten) => unlisten());
+      finishedUnlisten.then((unlisten) => unlisten());
+    };
   }, []);
 
   const Row = useCallback(
@@ -81,14 +121,53 @@ export function UnzipProgress() {
     [curr, Row],
   );
 
+  const percent = progress ? stagePercent(progress) : null;
+  const stageElapsedMs = progress
+    ? progress.elapsed_ms - (stageStart.current?.elapsedMs ?? 0)
+    : 0;
+  const etaMs =
+    percent && percent < 100
+      ? (stageElapsedMs / percent) * (100 - percent)
+      : null;
+
   return (
     <Dialog open={open}>
       <DialogContent showCloseButton={false}>
         <DialogHeader>
-          <DialogTitle className="pb-2">Progress</DialogTitle>
+          <DialogTitle className="pb-2">
+            {progress ? STAGE_LABELS[progress.stage] : "Progress"}
+          </DialogTitle>
+          {progress && (
+            <div className="space-y-2 pb-2 text-sm">
+              <Progress value={percent ?? 0} />
+              <div className="flex justify-between">
+                <span>
+                  {progress.extracted} / {progress.total_entries} entries
+                  extracted
+                </span>
+                <span>
+                  {formatDuration(progress.elapsed_ms)}
+                  {etaMs !== null && `, ~${formatDuration(etaMs)} left`}
+                </span>
+              </div>
+              <div>
+                {progress.decoded} decoded, {progress.skipped} skipped,{" "}
+                {progress.failed} failed
+              </div>
+            </div>
+          )}
           <code className="break-all rounded-md border p-2 text-sm">
             {memoizedList}
           </code>
+          <Button
+            variant="secondary"
+            size="sm"
+            className="mt-2"
+            disabled={cancelling}
+            onClick={cancelImport}
+          >
+            {cancelling ? "Cancelling..." : "Cancel Import"}
+          </Button>
         </DialogHeader>
       </DialogContent>
     </Dialog>
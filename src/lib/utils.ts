@@ -1,5 +1,6 @@
 import { clsx, type ClassValue } from "clsx";
 import { twMerge } from "tailwind-merge";
+import { AppError } from "@/types";
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
@@ -32,3 +33,12 @@ export function isNumber(value: any) {
 export function toString(o: Object) {
   return Object.fromEntries(Object.entries(o).map(([k, v]) => [k, String(v)]));
 }
+
+// Commands reject with an AppError, anything else is shown as is
+export function errorMessage(err: unknown): string {
+  if (err && typeof err === "object" && "message" in err) {
+    const { field, message } = err as AppError;
+    return field ? `${field}: ${message}` : message;
+  }
+  return String(err);
+}
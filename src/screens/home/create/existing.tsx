@@ -2,6 +2,7 @@ import { Button } from "@/components/ui/button";
 import { Label } from "@/components/ui/label";
 import { Textarea } from "@/components/ui/textarea";
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "@/lib/utils";
 import { open } from "@tauri-apps/plugin-dialog";
 import { useNavigate } from "react-router-dom";
 import { twMerge } from "tailwind-merge";
@@ -33,20 +34,15 @@ export const ImportExisting = ({
 
   async function addProject() {
     try {
-      const response: string = await invoke("create_project_existing", {
+      const id: string = await invoke("create_project_existing", {
         projectYmlPath,
       });
-      const data = await JSON.parse(response);
 
-      if (data.success) {
-        reset();
-        setDialogOpen(false);
-        navigate(`/project/${data.id}`);
-      } else {
-        setError(data.message);
-      }
+      reset();
+      setDialogOpen(false);
+      navigate(`/project/${id}`);
     } catch (error) {
-      setError(String(error));
+      setError(errorMessage(error));
       console.error("Error creating project:", error);
     }
   }
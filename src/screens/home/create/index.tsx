@@ -27,9 +27,12 @@ export const CreateProject = () => {
     createMcDirs: false,
     createRealmsDirs: false,
     createOfDirs: false,
+    importFormat: "labpbr-1.3",
+    keepOriginals: false,
   });
   const [error, setError] = useState<string | null>(null);
   const [zipPath, setZipPath] = useState<string | null>(null);
+  const [importDirPath, setImportDirPath] = useState<string | null>(null);
 
   const [projectYmlPath, setProjectYmlPath] = useState<string | null>(null);
 
@@ -42,9 +45,12 @@ export const CreateProject = () => {
       createMcDirs: false,
       createRealmsDirs: false,
       createOfDirs: false,
+      importFormat: "labpbr-1.3",
+      keepOriginals: false,
     });
     setError("");
     setZipPath("");
+    setImportDirPath("");
     setProjectYmlPath("");
   }
 
@@ -99,6 +105,8 @@ export const CreateProject = () => {
               setError={setError}
               zipPath={zipPath}
               setZipPath={setZipPath}
+              importDirPath={importDirPath}
+              setImportDirPath={setImportDirPath}
             />
           </TabsContent>
           <TabsContent
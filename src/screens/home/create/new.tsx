@@ -4,8 +4,16 @@ import { Button } from "@/components/ui/button";
 import { Checkbox } from "@/components/ui/checkbox";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
 import { Textarea } from "@/components/ui/textarea";
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "@/lib/utils";
 import { open } from "@tauri-apps/plugin-dialog";
 import { useState } from "react";
 import { useNavigate } from "react-router-dom";
@@ -24,6 +32,8 @@ export const NewProject = ({
   setError,
   zipPath,
   setZipPath,
+  importDirPath,
+  setImportDirPath,
 }: NewProjectProps) => {
   const navigate = useNavigate();
 
@@ -45,17 +55,27 @@ export const NewProject = ({
       canCreateDirectories: true,
       filters: [
         {
-          name: "Choose zip archive",
-          extensions: ["zip"],
+          name: "Choose zip archive or Bedrock pack",
+          extensions: ["zip", "mcpack"],
         },
       ],
     });
     setZipPath(dir);
+    setImportDirPath(null);
+  }
+
+  async function selectPackFolder() {
+    const dir = await open({
+      multiple: false,
+      directory: true,
+    });
+    setImportDirPath(dir);
+    setZipPath(null);
   }
 
   async function createProject() {
     try {
-      const response: string = await invoke("create_project", {
+      const id: string = await invoke("create_project", {
         dirPath: path,
         name: newProjectInfo.name || "My New Project",
         description: newProjectInfo.description,
@@ -63,18 +83,18 @@ export const NewProject = ({
         createRealmsDirs: newProjectInfo.createRealmsDirs,
         createOfDirs: newProjectInfo.createOfDirs,
         importZipPath: zipPath,
+        importDirPath: importDirPath,
+        importOptions: {
+          format: newProjectInfo.importFormat,
+          keep_originals: newProjectInfo.keepOriginals,
+        },
       });
-      const data: CreateProjectResponse = await JSON.parse(response);
 
-      if (data.success) {
-        reset();
-        setDialogOpen(false);
-        navigate(`/project/${data.id}`);
-      } else {
-        setError(data.message);
-      }
+      reset();
+      setDialogOpen(false);
+      navigate(`/project/${id}`);
     } catch (error) {
-      setError(String(error));
+      setError(errorMessage(error));
       console.error("Error creating project:", error);
     }
   }
@@ -239,6 +259,66 @@ export const NewProject = ({
                 {!zipPath ? "Open Archive" : "Change Archive"}
               </Button>
             </div>
+            <div className="mt-3 space-y-1">
+              <Label htmlFor="import-dir">Pack Folder (optional)</Label>
+              <Textarea
+                id="import-dir"
+                value={importDirPath || undefined}
+                disabled={!!importDirPath}
+                className={twMerge(
+                  "h-10 max-h-20 min-h-10 w-full max-w-[462px]",
+                  !importDirPath && "sr-only",
+                )}
+              />
+              <Button
+                onClick={selectPackFolder}
+                size="sm"
+                variant="secondary"
+                className="flex"
+                type="button"
+              >
+                {!importDirPath ? "Open Pack Folder" : "Change Pack Folder"}
+              </Button>
+            </div>
+            <div className="mt-3 space-y-1">
+              <Label htmlFor="import-format">Texture Format</Label>
+              <Select
+                value={newProjectInfo.importFormat}
+                onValueChange={(value) =>
+                  setNewProjectInfo({
+                    ...newProjectInfo,
+                    importFormat: value as ProjectInfo["importFormat"],
+                  })
+                }
+              >
+                <SelectTrigger id="import-format" className="w-[180px]">
+                  <SelectValue placeholder="Texture Format" />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="labpbr-1.3">LabPBR 1.3</SelectItem>
+                  <SelectItem value="labpbr-1.2">LabPBR 1.2</SelectItem>
+                  <SelectItem value="oldpbr">OldPBR / SEUS</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+            <div className="mt-3 flex items-center space-x-2">
+              <Checkbox
+                id="keep-originals"
+                checked={newProjectInfo.keepOriginals}
+                onCheckedChange={(e) =>
+                  setNewProjectInfo({
+                    ...newProjectInfo,
+                    keepOriginals: !!e,
+                  })
+                }
+              />
+              <label
+                htmlFor="keep-originals"
+                className="text-sm font-medium leading-none peer-disabled:cursor-not-allowed peer-disabled:opacity-70"
+              >
+                Keep original textures in .original
+              </label>
+            </div>
           </>
         )}
 
@@ -279,12 +359,6 @@ export const NewProject = ({
   );
 };
 
-interface CreateProjectResponse {
-  success: boolean;
-  id: string | null;
-  message: string;
-}
-
 interface NewProjectProps {
   reset: () => void;
   setDialogOpen: React.Dispatch<React.SetStateAction<boolean>>;
@@ -300,4 +374,7 @@ interface NewProjectProps {
 
   zipPath: string | null;
   setZipPath: React.Dispatch<React.SetStateAction<string | null>>;
+
+  importDirPath: string | null;
+  setImportDirPath: React.Dispatch<React.SetStateAction<string | null>>;
 }
@@ -17,6 +17,8 @@ import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 import { Eye, FolderOpen, MoreHorizontal, Trash2 } from "lucide-react";
 import { useEffect, useState } from "react";
 import { Link } from "react-router-dom";
+import { toast } from "sonner";
+import { errorMessage } from "@/lib/utils";
 import { CreateProject } from "./create";
 
 export function Home() {
@@ -31,8 +33,12 @@ export function Home() {
     await invoke("show_in_folder", { path });
 
   async function removeProject(id: string) {
-    const msg = await invoke("remove_project", { id });
-    if (msg) getProjects();
+    try {
+      await invoke("remove_project", { id });
+    } catch (err) {
+      toast(errorMessage(err));
+    }
+    getProjects();
   }
 
   useEffect(() => {
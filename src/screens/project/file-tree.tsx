@@ -42,9 +42,7 @@ export const FileTreeSidebar: React.FC<{
   }, [fileTree]);
 
   useEffect(() => {
-    listen<boolean>("unzip-started", (e) => {
-      if (!e.payload) getFileTree();
-    });
+    listen("import-finished", getFileTree);
   }, []);
 
   useDebounce(
@@ -10,6 +10,7 @@ import { useNavigate, useParams } from "react-router-dom";
 import { FileTreeSidebar } from "./file-tree";
 import { Interface } from "./interface";
 import { toast } from "sonner";
+import { errorMessage } from "@/lib/utils";
 
 export function ProjectPage() {
   const { id } = useParams();
@@ -46,7 +47,7 @@ export function ProjectPage() {
     } catch (err) {
       navigate("/");
       console.error(`Failed to open project: ${err}\nID: ${id}`);
-      toast("Failed to open project:" + String(err));
+      toast("Failed to open project: " + errorMessage(err));
     }
   }
 
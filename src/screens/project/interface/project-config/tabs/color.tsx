@@ -1,16 +1,163 @@
+import { Input } from "@/components/ui/input";
+import { errorMessage, isNumber, toString } from "@/lib/utils";
+import { AddAdditionalType } from "@/types";
+import { DefaultsColor } from "@/types/interface";
 import { invoke } from "@tauri-apps/api/core";
-import { useEffect } from "react";
+import React, { useEffect, useState } from "react";
+import { useNavigate } from "react-router-dom";
+import { toast } from "sonner";
+import { FieldLabel, useInheritedFields } from "./inherited";
+
+const fields: {
+  name: Exclude<keyof DefaultsColor, "tint">;
+  label: string;
+  step: string;
+  min?: number;
+  max?: number;
+}[] = [
+  { name: "hue_shift", label: "Hue Shift", step: "1" },
+  { name: "saturation", label: "Saturation", step: "0.01", min: 0 },
+  { name: "contrast", label: "Contrast", step: "0.01", min: 0 },
+  { name: "brightness", label: "Brightness", step: "0.01", min: -1, max: 1 },
+  { name: "red", label: "Red", step: "1", min: 0, max: 255 },
+  { name: "green", label: "Green", step: "1", min: 0, max: 255 },
+  { name: "blue", label: "Blue", step: "1", min: 0, max: 255 },
+];
 
 export function Color({ materialPath }: { materialPath: string }) {
-  useEffect(() => {
-    async function init() {
-      await invoke("select_texture_file", {
+  const navigate = useNavigate();
+  const [inherited, refreshInherited] = useInheritedFields(
+    materialPath,
+    "color",
+  );
+
+  const defaultValues: DefaultsColor = {
+    tint: "#ffffff",
+    hue_shift: 0,
+    saturation: 1,
+    contrast: 1,
+    brightness: 0,
+    red: 0,
+    green: 0,
+    blue: 0,
+  };
+
+  const [values, setValues] =
+    useState<AddAdditionalType<DefaultsColor, string>>(defaultValues);
+
+  async function updateDefaults(newValues: typeof values) {
+    try {
+      const { hue_shift, ...rest } = toString(newValues);
+      const res = await invoke("update_color", {
+        materialPath,
+        hueShift: hue_shift,
+        ...rest,
+      });
+      const parsedRes: string | boolean = await JSON.parse(String(res));
+      if (typeof parsedRes === "string") throw new Error(parsedRes);
+
+      if (parsedRes) {
+        init();
+      }
+    } catch (err) {
+      console.error(err);
+      toast(errorMessage(err));
+      navigate("/");
+    }
+  }
+
+  function onKeyDown(e: React.KeyboardEvent<HTMLInputElement>) {
+    if (e.key === "Escape" || e.key === "Enter") onBlur(e.currentTarget);
+  }
+
+  function onBlur(e: EventTarget & HTMLInputElement) {
+    const name = e.getAttribute("name")! as keyof DefaultsColor;
+    const field = fields.find((f) => f.name === name)!;
+    const value = e.value;
+    let newValues: typeof values;
+
+    try {
+      if (!isNumber(value)) throw new Error("Value is NaN");
+      if (!value || value.length < 1) throw new Error("Value is null");
+      let parsedValue = parseFloat(value);
+      if (field.min !== undefined) parsedValue = Math.max(field.min, parsedValue);
+      if (field.max !== undefined) parsedValue = Math.min(field.max, parsedValue);
+
+      newValues = { ...values, [name]: parsedValue };
+    } catch (err) {
+      console.log(err);
+      newValues = { ...values, [name]: defaultValues[name] };
+    }
+    setValues(newValues);
+    updateDefaults(newValues);
+  }
+
+  async function init() {
+    try {
+      const res: string = await invoke("select_texture_file", {
         materialPath,
         texture: "color",
       });
+      const parsedRes: Partial<DefaultsColor> | string = await JSON.parse(res);
+      if (typeof parsedRes === "string") throw new Error(parsedRes);
+
+      const merged = { ...defaultValues };
+      for (const [key, value] of Object.entries(parsedRes)) {
+        if (value !== null) Object.assign(merged, { [key]: value });
+      }
+      setValues(merged);
+      refreshInherited();
+    } catch (err) {
+      console.error(err);
+      toast(errorMessage(err));
+      navigate("/");
     }
+  }
 
+  useEffect(() => {
+    setValues(defaultValues);
     init();
   }, [materialPath]);
-  return <div>color</div>;
+
+  return (
+    <div className="flex flex-col gap-2 font-mono">
+      <div className="grid grid-cols-2 items-center justify-center px-2">
+        <FieldLabel source={inherited.tint}>Tint</FieldLabel>
+        <Input
+          className="h-8 p-0.5"
+          name="tint"
+          type="color"
+          value={values.tint ?? "#ffffff"}
+          onChange={(e) => {
+            setValues((prev) => ({ ...prev, tint: e.target.value }));
+          }}
+          onBlur={(e) => updateDefaults({ ...values, tint: e.target.value })}
+        />
+      </div>
+      {fields.map((field) => (
+        <div
+          key={field.name}
+          className="grid grid-cols-2 items-center justify-center px-2"
+        >
+          <FieldLabel source={inherited[field.name]}>
+            {field.label}
+          </FieldLabel>
+          <Input
+            className="h-max p-0.5 text-center"
+            name={field.name}
+            type="number"
+            step={field.step}
+            min={field.min}
+            max={field.max}
+            onKeyDown={onKeyDown}
+            onBlur={(e) => onBlur(e.currentTarget)}
+            value={values[field.name] ?? undefined}
+            onChange={(e) => {
+              setValues((prev) => ({ ...prev, [field.name]: e.target.value }));
+            }}
+          />
+        </div>
+      ))}
+    </div>
+  );
 }
@@ -1,12 +1,13 @@
+import { Checkbox } from "@/components/ui/checkbox";
 import { Input } from "@/components/ui/input";
-import { Label } from "@/components/ui/label";
-import { isNumber, toString } from "@/lib/utils";
+import { errorMessage, isNumber, toString } from "@/lib/utils";
 import { AddAdditionalType } from "@/types";
 import { DefaultsGrayscale } from "@/types/interface";
 import { invoke } from "@tauri-apps/api/core";
 import React, { useEffect, useState } from "react";
 import { useNavigate } from "react-router-dom";
 import { toast } from "sonner";
+import { FieldLabel, useInheritedFields } from "./inherited";
 
 export function Grayscale({
   materialPath,
@@ -18,8 +19,13 @@ export function Grayscale({
   textureFileOption: any;
 }) {
   const navigate = useNavigate();
+  const [inherited, refreshInherited] = useInheritedFields(
+    materialPath,
+    texture,
+  );
 
   const defaultValues: DefaultsGrayscale = {
+    invert: false,
     value: 0.0,
     shift: 0.0,
     scale: 1.0,
@@ -28,12 +34,12 @@ export function Grayscale({
   const [values, setValues] =
     useState<AddAdditionalType<DefaultsGrayscale, string>>(defaultValues);
 
-  async function updateDefaults() {
+  async function updateDefaults(newValues = values) {
     try {
       const res = await invoke("update_defaults_grayscale", {
         materialPath,
         texture,
-        ...toString(values),
+        ...toString(newValues),
       });
       const parsedRes: string | boolean = await JSON.parse(String(res));
       if (typeof parsedRes === "string") throw new Error(parsedRes);
@@ -43,7 +49,7 @@ export function Grayscale({
       }
     } catch (err) {
       console.error(err);
-      toast(String(err));
+      toast(errorMessage(err));
       navigate("/");
     }
   }
@@ -89,10 +95,11 @@ export function Grayscale({
       const parsedRes: DefaultsGrayscale | string = await JSON.parse(res);
       if (typeof parsedRes === "string") throw new Error(parsedRes);
 
-      setValues(parsedRes);
+      setValues({ ...parsedRes, invert: parsedRes.invert ?? false });
+      refreshInherited();
     } catch (err) {
       console.error(err);
-      toast(String(err));
+      toast(errorMessage(err));
       navigate("/");
     }
   }
@@ -103,8 +110,22 @@ export function Grayscale({
   }, [materialPath, textureOpt]);
   return (
     <div className="flex flex-col gap-2 font-mono">
+      <div className="flex items-center justify-center space-x-2 px-2">
+        <Checkbox
+          id="invert"
+          checked={values.invert === true}
+          onCheckedChange={(e) => {
+            const newValues = { ...values, invert: !!e };
+            setValues(newValues);
+            updateDefaults(newValues);
+          }}
+        />
+        <FieldLabel htmlFor="invert" source={inherited.invert}>
+          Invert
+        </FieldLabel>
+      </div>
       <div className="grid grid-cols-2 items-center justify-center px-2">
-        <Label className="text-center">Value</Label>
+        <FieldLabel source={inherited.value}>Value</FieldLabel>
         <Input
           className="h-max p-0.5 text-center"
           name="value"
@@ -120,7 +141,7 @@ export function Grayscale({
         />
       </div>
       <div className="grid grid-cols-2 items-center justify-center px-2">
-        <Label className="text-center">Shift</Label>
+        <FieldLabel source={inherited.shift}>Shift</FieldLabel>
         <Input
           className="h-max p-0.5 text-center"
           name="shift"
@@ -135,7 +156,7 @@ export function Grayscale({
         />
       </div>
       <div className="grid grid-cols-2 items-center justify-center px-2">
-        <Label className="text-center">Scale</Label>
+        <FieldLabel source={inherited.scale}>Scale</FieldLabel>
         <Input
           className="h-max p-0.5 text-center"
           name="scale"
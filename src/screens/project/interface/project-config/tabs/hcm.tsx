@@ -0,0 +1,132 @@
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { errorMessage } from "@/lib/utils";
+import {
+  DefaultsHcm,
+  HcmMetal,
+  hcmMetals,
+  MetalInfo,
+} from "@/types/interface";
+import { invoke } from "@tauri-apps/api/core";
+import React, { useEffect, useState } from "react";
+import { useNavigate } from "react-router-dom";
+import { toast } from "sonner";
+
+export function Hcm({ materialPath }: { materialPath: string }) {
+  const navigate = useNavigate();
+  const [metal, setMetal] = useState<HcmMetal | "">("");
+  const [mask, setMask] = useState("");
+  const [metals, setMetals] = useState<MetalInfo[]>([]);
+
+  async function updateMetal(newMetal: HcmMetal, newMask = mask) {
+    try {
+      const res = await invoke("update_hcm", {
+        materialPath,
+        metal: newMetal,
+        mask: newMask,
+      });
+      const parsedRes: string | boolean = await JSON.parse(String(res));
+      if (typeof parsedRes === "string") throw new Error(parsedRes);
+
+      if (parsedRes) {
+        init();
+      }
+    } catch (err) {
+      console.error(err);
+      toast(errorMessage(err));
+    }
+  }
+
+  function onKeyDown(e: React.KeyboardEvent<HTMLInputElement>) {
+    if (e.key === "Escape" || e.key === "Enter") e.currentTarget.blur();
+  }
+
+  async function init() {
+    try {
+      const res: string = await invoke("select_texture_file", {
+        materialPath,
+        texture: "hcm",
+      });
+      const parsedRes: DefaultsHcm | string = await JSON.parse(res);
+      if (typeof parsedRes === "string") throw new Error(parsedRes);
+
+      setMetal(parsedRes.metal ?? "");
+      setMask(parsedRes.mask ?? "");
+    } catch (err) {
+      console.error(err);
+      toast(errorMessage(err));
+      navigate("/");
+    }
+  }
+
+  useEffect(() => {
+    invoke<MetalInfo[]>("list_metals").then(setMetals).catch(console.error);
+  }, []);
+
+  useEffect(() => {
+    setMetal("");
+    setMask("");
+    init();
+  }, [materialPath]);
+
+  return (
+    <div className="flex flex-col gap-2 font-mono">
+      <div className="grid grid-cols-2 items-center justify-center px-2">
+        <Label className="text-center">Metal</Label>
+        <Select
+          name="metal"
+          value={metal}
+          onValueChange={(e) => {
+            setMetal(e as HcmMetal);
+            updateMetal(e as HcmMetal);
+          }}
+        >
+          <SelectTrigger>
+            <SelectValue placeholder="From hcm.png" />
+          </SelectTrigger>
+          <SelectContent>
+            {Object.entries(hcmMetals).map(([key, value]) => {
+              const preset = metals.find((m) => m.metal === key);
+              return (
+                <SelectItem key={key} value={key}>
+                  <span className="flex items-center gap-2">
+                    {preset && (
+                      <span
+                        className="inline-block size-3 rounded-sm border"
+                        style={{ backgroundColor: preset.color }}
+                      />
+                    )}
+                    {value}
+                    {preset && ` (${preset.index})`}
+                  </span>
+                </SelectItem>
+              );
+            })}
+          </SelectContent>
+        </Select>
+      </div>
+      <div className="grid grid-cols-2 items-center justify-center px-2">
+        <Label className="text-center">Mask</Label>
+        <Input
+          className="h-max p-0.5 text-center"
+          name="mask"
+          placeholder="Whole material"
+          disabled={!metal}
+          value={mask}
+          onKeyDown={onKeyDown}
+          onChange={(e) => setMask(e.target.value)}
+          onBlur={(e) => {
+            if (metal) updateMetal(metal, e.target.value);
+          }}
+        />
+      </div>
+    </div>
+  );
+}
@@ -1,38 +1,162 @@
+import { Checkbox } from "@/components/ui/checkbox";
 import { Input } from "@/components/ui/input";
-import { Label } from "@/components/ui/label";
+import { errorMessage, isNumber, toString } from "@/lib/utils";
+import { AddAdditionalType } from "@/types";
+import { DefaultsHeight } from "@/types/interface";
 import { invoke } from "@tauri-apps/api/core";
-import React, { useEffect } from "react";
+import React, { useEffect, useState } from "react";
+import { useNavigate } from "react-router-dom";
+import { toast } from "sonner";
+import { FieldLabel, useInheritedFields } from "./inherited";
+
+const fields: {
+  name: Exclude<keyof DefaultsHeight, "invert">;
+  label: string;
+  step: string;
+  min?: number;
+  max?: number;
+}[] = [
+  { name: "value", label: "Value", step: "1", min: 0, max: 255 },
+  { name: "shift", label: "Shift", step: "0.001" },
+  { name: "scale", label: "Scale", step: "0.001" },
+  { name: "blur_radius", label: "Blur Radius", step: "0.1", min: 0 },
+  { name: "edge_fade", label: "Edge Fade", step: "0.5", min: 0 },
+];
 
 export function Height({ materialPath }: { materialPath: string }) {
-  function submitHandler(e: React.FormEvent<HTMLFormElement>) {
-    e.preventDefault();
+  const navigate = useNavigate();
+  const [inherited, refreshInherited] = useInheritedFields(
+    materialPath,
+    "height",
+  );
+
+  const defaultValues: DefaultsHeight = {
+    invert: false,
+    value: 0,
+    shift: 0,
+    scale: 1,
+    blur_radius: 0,
+    edge_fade: 0,
+  };
+
+  const [values, setValues] =
+    useState<AddAdditionalType<DefaultsHeight, string>>(defaultValues);
+
+  async function updateDefaults(newValues: typeof values) {
+    try {
+      const { blur_radius, edge_fade, ...rest } = toString(newValues);
+      const res = await invoke("update_height", {
+        materialPath,
+        blurRadius: blur_radius,
+        edgeFade: edge_fade,
+        ...rest,
+      });
+      const parsedRes: string | boolean = await JSON.parse(String(res));
+      if (typeof parsedRes === "string") throw new Error(parsedRes);
+
+      if (parsedRes) {
+        init();
+      }
+    } catch (err) {
+      console.error(err);
+      toast(errorMessage(err));
+      navigate("/");
+    }
   }
 
-  useEffect(() => {
-    async function init() {
-      await invoke("select_texture_file", {
+  function onKeyDown(e: React.KeyboardEvent<HTMLInputElement>) {
+    if (e.key === "Escape" || e.key === "Enter") onBlur(e.currentTarget);
+  }
+
+  function onBlur(e: EventTarget & HTMLInputElement) {
+    const name = e.getAttribute("name")! as keyof DefaultsHeight;
+    const field = fields.find((f) => f.name === name)!;
+    const value = e.value;
+    let newValues: typeof values;
+
+    try {
+      if (!isNumber(value)) throw new Error("Value is NaN");
+      if (!value || value.length < 1) throw new Error("Value is null");
+      let parsedValue = parseFloat(value);
+      if (field.min !== undefined) parsedValue = Math.max(field.min, parsedValue);
+      if (field.max !== undefined) parsedValue = Math.min(field.max, parsedValue);
+
+      newValues = { ...values, [name]: parsedValue };
+    } catch (err) {
+      console.log(err);
+      newValues = { ...values, [name]: defaultValues[name] };
+    }
+    setValues(newValues);
+    updateDefaults(newValues);
+  }
+
+  async function init() {
+    try {
+      const res: string = await invoke("select_texture_file", {
         materialPath,
         texture: "height",
       });
+      const parsedRes: Partial<DefaultsHeight> | string = await JSON.parse(res);
+      if (typeof parsedRes === "string") throw new Error(parsedRes);
+
+      const merged = { ...defaultValues };
+      for (const [key, value] of Object.entries(parsedRes)) {
+        if (value !== null) Object.assign(merged, { [key]: value });
+      }
+      setValues(merged);
+      refreshInherited();
+    } catch (err) {
+      console.error(err);
+      toast(errorMessage(err));
+      navigate("/");
     }
+  }
 
+  useEffect(() => {
+    setValues(defaultValues);
     init();
   }, [materialPath]);
 
   return (
-    <form className="flex flex-col gap-2 font-mono" onSubmit={submitHandler}>
-      <div className="grid grid-cols-2 items-center justify-center px-2">
-        <Label className="text-center">Value</Label>
-        <Input className="h-max p-0.5 text-center" name="value" type="number" />
-      </div>
-      <div className="grid grid-cols-2 items-center justify-center px-2">
-        <Label className="text-center">Shift</Label>
-        <Input className="h-max p-0.5 text-center" name="shift" type="number" />
-      </div>
-      <div className="grid grid-cols-2 items-center justify-center px-2">
-        <Label className="text-center">Scale</Label>
-        <Input className="h-max p-0.5 text-center" name="scale" type="number" />
+    <div className="flex flex-col gap-2 font-mono">
+      <div className="flex items-center justify-center space-x-2 px-2">
+        <Checkbox
+          id="invert"
+          checked={values.invert === true}
+          onCheckedChange={(e) => {
+            const newValues = { ...values, invert: !!e };
+            setValues(newValues);
+            updateDefaults(newValues);
+          }}
+        />
+        <FieldLabel htmlFor="invert" source={inherited.invert}>
+          Invert
+        </FieldLabel>
       </div>
-    </form>
+      {fields.map((field) => (
+        <div
+          key={field.name}
+          className="grid grid-cols-2 items-center justify-center px-2"
+        >
+          <FieldLabel source={inherited[field.name]}>
+            {field.label}
+          </FieldLabel>
+          <Input
+            className="h-max p-0.5 text-center"
+            name={field.name}
+            type="number"
+            step={field.step}
+            min={field.min}
+            max={field.max}
+            onKeyDown={onKeyDown}
+            onBlur={(e) => onBlur(e.currentTarget)}
+            value={values[field.name] ?? undefined}
+            onChange={(e) => {
+              setValues((prev) => ({ ...prev, [field.name]: e.target.value }));
+            }}
+          />
+        </div>
+      ))}
+    </div>
   );
 }
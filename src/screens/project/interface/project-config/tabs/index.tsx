@@ -3,6 +3,7 @@
 
 import { Color } from "./color";
 import { Grayscale } from "./grayscale";
+import { Hcm } from "./hcm";
 import { Height } from "./height";
 import { Normal } from "./normal";
 
@@ -47,6 +48,16 @@ export function DefaultTab({
       );
       break;
 
+    case "Occlusion":
+      Component = (
+        <Grayscale
+          materialPath={materialPath}
+          texture="occlusion"
+          textureFileOption={textureFileOption}
+        />
+      );
+      break;
+
     case "Smoothness":
       Component = (
         <Grayscale
@@ -77,6 +88,10 @@ export function DefaultTab({
       );
       break;
 
+    case "HCM":
+      Component = <Hcm materialPath={materialPath} />;
+      break;
+
     case "F0":
       Component = (
         <Grayscale
@@ -0,0 +1,51 @@
+import { Label } from "@/components/ui/label";
+import { invoke } from "@tauri-apps/api/core";
+import React, { useCallback, useEffect, useState } from "react";
+
+// Fields of a mat.yml section that come from a template, mapped to the
+// template they're read from
+export function useInheritedFields(materialPath: string, section: string) {
+  const [fields, setFields] = useState<Record<string, string>>({});
+
+  const refresh = useCallback(async () => {
+    try {
+      const res: Record<string, string> = await invoke("get_inherited_fields", {
+        materialPath,
+        section,
+      });
+      setFields(res);
+    } catch (err) {
+      console.error(err);
+      setFields({});
+    }
+  }, [materialPath, section]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  return [fields, refresh] as const;
+}
+
+export function FieldLabel({
+  children,
+  source,
+  htmlFor,
+}: {
+  children: React.ReactNode;
+  source?: string;
+  htmlFor?: string;
+}) {
+  return (
+    <Label
+      className="text-center"
+      htmlFor={htmlFor}
+      title={source ? `Inherited from ${source}` : "Set in mat.yml"}
+    >
+      {children}
+      {source && (
+        <span className="ml-1 text-xs text-muted-foreground">(inherited)</span>
+      )}
+    </Label>
+  );
+}
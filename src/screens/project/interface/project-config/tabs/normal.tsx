@@ -1,5 +1,4 @@
 import { Input } from "@/components/ui/input";
-import { Label } from "@/components/ui/label";
 import {
   Select,
   SelectContent,
@@ -7,13 +6,14 @@ import {
   SelectTrigger,
   SelectValue,
 } from "@/components/ui/select";
-import { isNumber, toString } from "@/lib/utils";
+import { errorMessage, isNumber, toString } from "@/lib/utils";
 import { AddAdditionalType } from "@/types";
 import { kernelSizes, NormalMap } from "@/types/interface";
 import { invoke } from "@tauri-apps/api/core";
 import { useEffect, useState } from "react";
 import { useNavigate } from "react-router-dom";
 import { toast } from "sonner";
+import { FieldLabel, useInheritedFields } from "./inherited";
 
 export function Normal({
   materialPath,
@@ -23,6 +23,10 @@ export function Normal({
   textureFileOption: any;
 }) {
   const navigate = useNavigate();
+  const [inherited, refreshInherited] = useInheritedFields(
+    materialPath,
+    "normal",
+  );
 
   const defaultValues: NormalMap = {
     curveX: 0,
@@ -52,7 +56,7 @@ export function Normal({
       }
     } catch (err) {
       console.error(err);
-      toast(String(err));
+      toast(errorMessage(err));
       navigate("/");
     }
   }
@@ -104,9 +108,10 @@ export function Normal({
         method: parsedRes.method,
         strength: parsedRes.strength,
       });
+      refreshInherited();
     } catch (err) {
       console.error(err);
-      toast(String(err));
+      toast(errorMessage(err));
       navigate("/");
     }
   }
@@ -124,7 +129,7 @@ export function Normal({
         </h3>
         <div className="flex flex-col gap-2 font-mono">
           <div className="grid grid-cols-2 items-center justify-center px-2">
-            <Label className="text-center">Curve X</Label>
+            <FieldLabel source={inherited.curve_x}>Curve X</FieldLabel>
             <Input
               className="h-max p-0.5 text-center"
               name="curve-X"
@@ -141,7 +146,7 @@ export function Normal({
             />
           </div>
           <div className="grid grid-cols-2 items-center justify-center px-2">
-            <Label className="text-center">Curve Y</Label>
+            <FieldLabel source={inherited.curve_y}>Curve Y</FieldLabel>
             <Input
               className="h-max p-0.5 text-center"
               name="curveY"
@@ -159,7 +164,7 @@ export function Normal({
           </div>
           <div aria-hidden className="h-2" />
           <div className="grid grid-cols-2 items-center justify-center px-2">
-            <Label className="text-center">Radius Size X</Label>
+            <FieldLabel source={inherited.radius_size_x}>Radius Size X</FieldLabel>
             <Input
               className="h-max p-0.5 text-center"
               name="radiusSizeX"
@@ -176,7 +181,7 @@ export function Normal({
             />
           </div>
           <div className="grid grid-cols-2 items-center justify-center px-2">
-            <Label className="text-center">Radius Size Y</Label>
+            <FieldLabel source={inherited.radius_size_y}>Radius Size Y</FieldLabel>
             <Input
               className="h-max p-0.5 text-center"
               name="radiusSizeY"
@@ -194,7 +199,7 @@ export function Normal({
           </div>
           <div aria-hidden className="h-2" />
           <div className="grid grid-cols-2 items-center justify-center px-2">
-            <Label className="text-center">Noise Angle</Label>
+            <FieldLabel source={inherited.noise_angle}>Noise Angle</FieldLabel>
             <Input
               className="h-max p-0.5 text-center"
               name="noiseAngle"
@@ -217,7 +222,7 @@ export function Normal({
         </h3>
         <div className="flex flex-col gap-2 font-mono">
           <div className="grid grid-cols-2 items-center justify-center px-2">
-            <Label className="text-center">Method</Label>
+            <FieldLabel source={inherited.method}>Method</FieldLabel>
             <Select
               name="method"
               value={String(values.method)}
@@ -245,7 +250,7 @@ export function Normal({
             </Select>
           </div>
           <div className="grid grid-cols-2 items-center justify-center px-2">
-            <Label className="text-center">Strength</Label>
+            <FieldLabel source={inherited.strength}>Strength</FieldLabel>
             <Input
               className="h-max p-0.5 text-center"
               name="strength"
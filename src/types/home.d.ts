@@ -13,4 +13,41 @@ export interface ProjectInfo {
   createMcDirs: boolean;
   createRealmsDirs: boolean;
   createOfDirs: boolean;
+  importFormat: "labpbr-1.3" | "labpbr-1.2" | "oldpbr";
+  keepOriginals: boolean;
+}
+
+export type ImportStage =
+  | "extracting"
+  | "converting"
+  | "texture-sets"
+  | "finishing";
+
+export interface ImportProgress {
+  project_id: string;
+  stage: ImportStage;
+  total_entries: number;
+  extracted: number;
+  processed: number;
+  decoded: number;
+  skipped: number;
+  failed: number;
+  current: string | null;
+  elapsed_ms: number;
+}
+
+export interface ImportSummary {
+  project_id: string;
+  success: boolean;
+  cancelled: boolean;
+  error: string | null;
+  decoded: number;
+  skipped: number;
+  failed: number;
+  elapsed_ms: number;
+  report: {
+    converted: string[];
+    skipped: { path: string; reason: string }[];
+    failed: { path: string; error: string }[];
+  } | null;
 }
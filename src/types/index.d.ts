@@ -1,3 +1,18 @@
+// What every failing command rejects with
+export type AppError = {
+  kind:
+    | "not-found"
+    | "parse"
+    | "io"
+    | "invalid-field"
+    | "invalid-input"
+    | "conflict";
+  path: string;
+  // Only set for invalid-field, e.g. "normal.method"
+  field?: string;
+  message: string;
+};
+
 export type AddAdditionalType<T, U> = {
   [K in keyof T]: T[K] | U;
 };
@@ -17,11 +17,61 @@ export const textureFilesOptions = [
 export type TextureFilesTypes = (typeof textureFilesOptions)[number];
 
 export interface DefaultsGrayscale {
+  invert: boolean | null;
   value: number | null;
   shift: number | null;
   scale: number | null;
 }
 
+export const hcmMetals = {
+  iron: "Iron",
+  gold: "Gold",
+  aluminum: "Aluminum",
+  chrome: "Chrome",
+  copper: "Copper",
+  lead: "Lead",
+  platinum: "Platinum",
+  silver: "Silver",
+  "custom-f0": "Custom F0",
+};
+export type HcmMetal = keyof typeof hcmMetals;
+
+export interface DefaultsHcm {
+  metal: HcmMetal | null;
+  mask?: string | null;
+}
+
+// A hardcoded metal preset, see the list_metals command
+export interface MetalInfo {
+  metal: HcmMetal;
+  name: string;
+  index: number;
+  n: [number, number, number];
+  k: [number, number, number];
+  f0: [number, number, number];
+  color: string;
+}
+
+export interface DefaultsHeight {
+  invert: boolean | null;
+  value: number | null;
+  shift: number | null;
+  scale: number | null;
+  blur_radius: number | null;
+  edge_fade: number | null;
+}
+
+export interface DefaultsColor {
+  tint: string | null;
+  hue_shift: number | null;
+  saturation: number | null;
+  contrast: number | null;
+  brightness: number | null;
+  red: number | null;
+  green: number | null;
+  blue: number | null;
+}
+
 export const kernelSizes = {
   0: "Sobel 3x3",
   1: "Sobel 5x5",
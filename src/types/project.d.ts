@@ -3,3 +3,17 @@ export interface FileTree {
   is_mat: boolean;
   children: FileTree[];
 }
+
+// A publish profile from project.yml, see the list_profiles command
+export interface Profile {
+  edition: "Java" | "Bedrock";
+  format: number;
+  name: string;
+  description?: string | null;
+  block_texture_size?: number | null;
+  item_texture_size?: number | null;
+  texture_scale?: number | null;
+  auto_level_height?: boolean | null;
+  bake_occlusion_texture?: boolean | null;
+  output_format?: "labpbr-1.3" | "oldpbr" | null;
+}